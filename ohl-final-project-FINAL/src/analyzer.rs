@@ -1,15 +1,19 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::ops::{Deref};
 use std::rc::Rc;
+use crate::error::{Error, Result};
 use crate::frame_analyze::{AFrame, ASymbol, FrameTyp};
 use crate::log::Log;
 use crate::mtree::MTree;
 use crate::token::{TCode, Token};
+use crate::typ::Typ;
 use crate::value::DValue;
 
 
 pub struct Analyzer {
     pub log: Log,
+    loop_depth: Cell<usize>,                    // number of loops around current statement
+    decl_typ: Cell<FrameTyp>,                   // storage of variables declared with let
 }
 
 impl Analyzer {
@@ -17,17 +21,23 @@ impl Analyzer {
     pub fn new() -> Analyzer {
         Analyzer {
             log: Log::new(),
+            loop_depth: Cell::new(0),
+            decl_typ: Cell::new(FrameTyp::FUNCTION),
         }
     }
 
-    pub fn analyze_global(&self, rc_mtree_global : Rc<MTree>) -> Rc<MTree> {
+    pub fn analyze_global(&self, rc_mtree_global : Rc<MTree>) -> Result<Rc<MTree>> {
         let rcc_frame = Rc::new(RefCell::new(AFrame::new()));
-        self.analyze_block(rc_mtree_global.deref(), rcc_frame)
+        // global variables live as long as the program
+        let decl_typ = self.decl_typ.replace(FrameTyp::PROGRAM);
+        let result = self.analyze_block(rc_mtree_global.deref(), rcc_frame);
+        self.decl_typ.set(decl_typ);
+        result
     }
 
     pub fn analyze_block(
         &self, mtree_block: &MTree, rcc_frame_parent: Rc<RefCell<AFrame>>)
-        -> Rc<MTree>
+        -> Result<Rc<MTree>>
     {
         // create AFrame
         let frame_block = AFrame::new_child(rcc_frame_parent);
@@ -36,43 +46,49 @@ impl Analyzer {
         self.analyze_block_framed(mtree_block, rcc_frame_block)
     }
 
-    pub fn analyze_block_framed(&self, mtree_block: &MTree, rcc_frame_block: Rc<RefCell<AFrame>>) -> Rc<MTree> {
+    pub fn analyze_block_framed(&self, mtree_block: &MTree, rcc_frame_block: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
         mtree_block.print();
         // collect symbols
-        let token_block_ = Token::from(TCode::A_BLOCK(rcc_frame_block.clone()));
+        let token_block_ = Token {
+            code: TCode::A_BLOCK(rcc_frame_block.clone()),
+            loc: mtree_block.token.loc.clone(),
+        };
         let mut tree_block_ = MTree::new(token_block_);
 
         for rc_stmt in &mtree_block.children {
             let rc_stmt_ = self.analyze_stmt(
                 rc_stmt.deref(),
                 rcc_frame_block.clone()
-            );
+            )?;
             tree_block_.children.push(rc_stmt_)
         };
 
-        Rc::new(tree_block_)
+        Ok(Rc::new(tree_block_))
     }
 
-    pub fn analyze_stmt(&self, tree_stmt: &MTree, rcc_frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
+    pub fn analyze_stmt(&self, tree_stmt: &MTree, rcc_frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
         match tree_stmt.token.code {
             TCode::FUNC => { self.analyze_func(tree_stmt, rcc_frame) }
             TCode::IF => { self.analyze_if(tree_stmt, rcc_frame) }
             TCode::WHILE => { self.analyze_while(tree_stmt, rcc_frame) }
+            TCode::FOR => { self.analyze_for(tree_stmt, rcc_frame) }
             TCode::RETURN => { self.analyze_return(tree_stmt, rcc_frame) }
+            TCode::BREAK | TCode::CONTINUE => { self.analyze_jump(tree_stmt) }
             TCode::ASSIGN => { self.analyze_assign(tree_stmt, rcc_frame) }
             TCode::A_BLOCK(_) => { self.analyze_block(tree_stmt, rcc_frame) }
             TCode::READ => { self.analyze_read(tree_stmt, rcc_frame) }
             TCode::WRITE => { self.analyze_write(tree_stmt, rcc_frame) }
             TCode::CALL => { self.analyze_call(tree_stmt, rcc_frame) }
-            TCode::LET => { self.analyze_let(tree_stmt, rcc_frame) }
+            TCode::LET | TCode::MUT | TCode::CONST => { self.analyze_let(tree_stmt, rcc_frame) }
             _ => {
-                panic!("Code {:?}", tree_stmt.token.code);
+                Err(Error::semantic(format!("Invalid statement {:?}", tree_stmt.token.code))
+                    .at(tree_stmt.token.loc.clone()))
             }
         }
 
     }
 
-    pub fn analyze_func(&self, mtree_func: &MTree, rcc_frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
+    pub fn analyze_func(&self, mtree_func: &MTree, rcc_frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
         // create new block frame (statics)
         let mut frame_func_ = AFrame::new_child(rcc_frame.clone());
 
@@ -83,11 +99,15 @@ impl Analyzer {
             self.log.debug(format!("analyze_func() '{:}'", name_func).as_str());
 
             let rc = Rc::new(MTree::new( mtree_id.token.clone()));
+            let n_params = mtree_func.children.get(1).unwrap().children.len();
             let mut symbol = ASymbol::new(name_func.clone(), FrameTyp::PROGRAM);
             symbol.value = DValue::FUNC(rc); // placeholder
+            symbol.typ = Typ::func(n_params);
+            symbol.pos = mtree_id.token.loc.first.clone();
             rcc_frame.borrow_mut().symbol_new(symbol)
         } else {
-            panic!("Missing function ID in FUNC MTree!")
+            return Err(Error::semantic("Missing function ID in FUNC MTree!")
+                .at(mtree_func.token.loc.clone()));
         };
 
         // collect symbols
@@ -96,23 +116,35 @@ impl Analyzer {
             let mtree_param = rc_mtree_param.deref();
             match & mtree_param.token.code {
                 TCode::ID(name) => {
-                    let symbol = ASymbol::new(name.clone(), FrameTyp::FUNCTION);
+                    // parameters can be assigned
+                    let mut symbol = ASymbol::new(name.clone(), FrameTyp::FUNCTION);
+                    symbol.mutable = true;
+                    symbol.pos = mtree_param.token.loc.first.clone();
                     frame_func_.symbol_new(symbol);
                 }
-                _ => { panic!("Illegal Params Tree") }
+                _ => {
+                    return Err(Error::semantic("Illegal Params Tree")
+                        .at(mtree_param.token.loc.clone()))
+                }
             }
         }
 
-        // analyze block
+        // analyze block (loops around function don't count for break/continue)
         let rc_block = mtree_func.children.get(2).unwrap().deref();
-        let rc_block_ = self.analyze_block_framed(
+        let loop_depth = self.loop_depth.replace(0);
+        let decl_typ = self.decl_typ.replace(FrameTyp::FUNCTION);
+        let result_block = self.analyze_block_framed(
             rc_block,
             Rc::new(RefCell::new(frame_func_))
         );
-
-        // create new tree node
-        let mut mtree_func_ = MTree::new(Token::from(TCode::FUNC));
-        mtree_func_.children.insert(0, rc_block_);
+        self.loop_depth.set(loop_depth);
+        self.decl_typ.set(decl_typ);
+        let rc_block_ = result_block?;
+
+        // create new tree node (parameters are kept for arity check of calls)
+        let mut mtree_func_ = MTree::new(mtree_func.token.clone());
+        mtree_func_.children.push(rc_block_);
+        mtree_func_.children.push(mtree_func.children.get(1).unwrap().clone());
         let rc_mtree_func_ = Rc::new(mtree_func_);
 
         // set code (value) of function
@@ -120,38 +152,44 @@ impl Analyzer {
             DValue::FUNC(rc_mtree_func_.clone());
 
         // return func
-        rc_mtree_func_
+        Ok(rc_mtree_func_)
     }
 
-    pub fn analyze_if(&self, mtree_if: &MTree, frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
-
-        let mtree_cond = mtree_if.children.get(0).unwrap().deref();
-        let mtree_then = mtree_if.children.get(1).unwrap().deref();
-        let mtree_else = mtree_if.children.get(2).unwrap().deref();
-        let rc_cond = self.analyze_expr(mtree_cond, frame.clone());
-        let rc_true = self.analyze_block(mtree_then, frame.clone());
-        let rc_false = self.analyze_block(mtree_else, frame.clone());
+    pub fn analyze_if(&self, mtree_if: &MTree, frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
 
+        // pairs of condition and block, optionally followed by else block
         let token_if = Token {
             code: TCode::IF,
             loc: mtree_if.token.loc.clone(),
         };
         let mut mtree_if_ = MTree::new( token_if);
-        mtree_if_.children.push(rc_cond);
-        mtree_if_.children.push(rc_true);
-        mtree_if_.children.push(rc_false);
+        for branch in mtree_if.children.chunks(2) {
+            match branch {
+                [rc_cond, rc_block] => {
+                    mtree_if_.children.push(self.analyze_expr(rc_cond.deref(), frame.clone())?);
+                    mtree_if_.children.push(self.analyze_block(rc_block.deref(), frame.clone())?);
+                }
+                [rc_else] => {
+                    mtree_if_.children.push(self.analyze_block(rc_else.deref(), frame.clone())?);
+                }
+                _ => {}
+            }
+        }
 
-        Rc::new(mtree_if_)
+        Ok(Rc::new(mtree_if_))
     }
 
-    pub fn analyze_while(&self, _mtree_while: &MTree, _frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
+    pub fn analyze_while(&self, _mtree_while: &MTree, _frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
         // <while> -> WHILE ID <relational operator> <point of comparison> <block>
 
         let mtree_cond = _mtree_while.children.get(0).unwrap().deref();
         let mtree_while_body = _mtree_while.children.get(1).unwrap().deref();
 
-        let rc_cond = self.analyze_expr(mtree_cond, _frame.clone());
-        let rc_while_body = self.analyze_block(mtree_while_body, _frame.clone());
+        let rc_cond = self.analyze_expr(mtree_cond, _frame.clone())?;
+        self.loop_depth.set(self.loop_depth.get() + 1);
+        let result_body = self.analyze_block(mtree_while_body, _frame.clone());
+        self.loop_depth.set(self.loop_depth.get() - 1);
+        let rc_while_body = result_body?;
 
         let token_while = Token {
             code: TCode::WHILE,
@@ -163,32 +201,92 @@ impl Analyzer {
         mtree_while.children.push(rc_cond);
         mtree_while.children.push(rc_while_body);
 
-        Rc::new(mtree_while)
+        Ok(Rc::new(mtree_while))
+    }
+
+    pub fn analyze_for(&self, mtree_for: &MTree, frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
+        // FOR ID ( range | expr ) block
+        let mtree_id = mtree_for.children.first().unwrap().deref();
+        let mtree_iterable = mtree_for.children.get(1).unwrap().deref();
+        let mtree_body = mtree_for.children.get(2).unwrap().deref();
+
+        // range (or iterable) is evaluated outside of loop
+        let rc_iterable_ = match mtree_iterable.token.code {
+            TCode::DOT_DOT | TCode::DOT_DOT_EQ => {
+                let mut mtree_range_ = MTree::new(mtree_iterable.token.clone());
+                for rc_bound in &mtree_iterable.children {
+                    mtree_range_.children.push(self.analyze_expr(rc_bound.deref(), frame.clone())?);
+                }
+                Rc::new(mtree_range_)
+            }
+            _ => { self.analyze_expr(mtree_iterable, frame.clone())? }
+        };
+
+        // loop variable is scoped to frame of body
+        let TCode::ID(name) = &mtree_id.token.code else {
+            return Err(Error::semantic("Expect identifier as variable of for loop!")
+                .at(mtree_id.token.loc.clone()));
+        };
+        let mut frame_body = AFrame::new_child(frame);
+        let mut symbol = ASymbol::new(name.clone(), FrameTyp::FUNCTION);
+        symbol.pos = mtree_id.token.loc.first.clone();
+        let loc = frame_body.symbol_new(symbol);
+
+        self.loop_depth.set(self.loop_depth.get() + 1);
+        let result_body = self.analyze_block_framed(mtree_body, Rc::new(RefCell::new(frame_body)));
+        self.loop_depth.set(self.loop_depth.get() - 1);
+        let rc_body_ = result_body?;
+
+        let token_ref = Token {
+            code: TCode::A_REF(loc),
+            loc: mtree_id.token.loc.clone(),
+        };
+        let mut mtree_for_ = MTree::new(mtree_for.token.clone());
+        mtree_for_.children.push(Rc::new(MTree::new(token_ref)));
+        mtree_for_.children.push(rc_iterable_);
+        mtree_for_.children.push(rc_body_);
+
+        Ok(Rc::new(mtree_for_))
     }
 
-    pub fn analyze_return(&self, mtree_return: &MTree, frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
-        let rc_expr = mtree_return.children.get(0).unwrap();
-        self.analyze_expr(rc_expr.deref(), frame)
+    pub fn analyze_return(&self, mtree_return: &MTree, frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
+        let mut mtree_return_ = MTree::new(mtree_return.token.clone());
+        // RETURN [ expr ]
+        if let Some(rc_expr) = mtree_return.children.first() {
+            mtree_return_.children.push(self.analyze_expr(rc_expr.deref(), frame)?);
+        }
+        Ok(Rc::new(mtree_return_))
+    }
+
+    /// break or continue (only inside of loop)
+    pub fn analyze_jump(&self, mtree_jump: &MTree) -> Result<Rc<MTree>> {
+        if self.loop_depth.get() == 0 {
+            return Err(Error::semantic(format!("{:?} outside of loop!", mtree_jump.token.code))
+                .at(mtree_jump.token.loc.clone()));
+        }
+        Ok(Rc::new(MTree::new(mtree_jump.token.clone())))
     }
 
-    pub fn analyze_assign(&self, mtree_assign: &MTree, rcc_frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
+    pub fn analyze_assign(&self, mtree_assign: &MTree, rcc_frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
         // expr on RHS
         let mtree_expr = mtree_assign.children.get(1).unwrap().deref();
-        let rc_mtree_expr_ = self.analyze_expr(mtree_expr, rcc_frame.clone());
+        let rc_mtree_expr_ = self.analyze_expr(mtree_expr, rcc_frame.clone())?;
 
         // symbol on LHS
         let mtree_id = mtree_assign.children.get(0).unwrap().deref();
         let loc = if let TCode::ID(name) = & mtree_id.token.code {
-            // lookup or create symbol
+            // variable must be declared (and not be const)
             let option_symbol = rcc_frame.borrow().symbol_lookup(name);
             if let Some(symbol) = option_symbol {
+                self.check_mutable(&symbol, mtree_id)?;
                 symbol.loc
             } else {
-                let symbol = ASymbol::new(name.clone(), FrameTyp::FUNCTION);
-                rcc_frame.borrow_mut().symbol_new(symbol)
+                return Err(Error::semantic(format!("Assignment to undeclared variable '{}'!", name))
+                    .at(mtree_id.token.loc.clone()));
             }
         } else {
-            panic!("Expect identifier on LHS of assignment!");
+            return Err(Error::semantic("Expect identifier on LHS of assignment!")
+                .at(mtree_id.token.loc.clone()));
         };
         let token_ref = Token {
             code: TCode::A_REF(loc.clone()),
@@ -203,58 +301,84 @@ impl Analyzer {
                 children: vec![ rc_mtree_ref, rc_mtree_expr_ ],
             }
         );
-        rc_mtree_assign_
+        Ok(rc_mtree_assign_)
     }
 
-    pub fn analyze_write(&self, mtree_write: &MTree, frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
+    pub fn analyze_write(&self, mtree_write: &MTree, frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
         mtree_write.print();
         let rc_mtree_expr = mtree_write.children.get(0).unwrap();
-        let rc_mtree_expr_ = self.analyze_expr(rc_mtree_expr.deref(), frame);
-        Rc::new(MTree {
+        let rc_mtree_expr_ = self.analyze_expr(rc_mtree_expr.deref(), frame)?;
+        Ok(Rc::new(MTree {
             token: mtree_write.token.clone(),
             children: vec![rc_mtree_expr_],
-        })
+        }))
     }
 
-    pub fn analyze_read(&self, mtree_read: &MTree, frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
-        let rc_mtree_id = mtree_read.children.get(0).unwrap();
-        let rc_mtree_id_ = self.analyze_id_load(rc_mtree_id.deref(), frame);
-        Rc::new(MTree {
+    pub fn analyze_read(&self, mtree_read: &MTree, frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
+        let rc_mtree_id = mtree_read.children.first().unwrap();
+        if let TCode::ID(name) = &rc_mtree_id.token.code
+            && let Some(symbol) = frame.borrow().symbol_lookup(name)
+        {
+            self.check_mutable(&symbol, rc_mtree_id)?;
+        }
+        let rc_mtree_id_ = self.analyze_id_load(rc_mtree_id.deref(), frame)?;
+        Ok(Rc::new(MTree {
             token: mtree_read.token.clone(),
             children: vec![rc_mtree_id_],
-        })
+        }))
     }
 
-    fn analyze_let(&self, mtree_let: &MTree, frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
-        let rc_mtree_id = mtree_let.children.get(0).unwrap();
+    fn analyze_let(&self, mtree_let: &MTree, frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
+        let mtree_id = mtree_let.children.first().unwrap().deref();
+
+        // LET ID ASSIGN expr SEMICOLON: initializer still sees shadowed variable
+        let option_expr_ = match mtree_let.children.get(1) {
+            Some(rc_mtree_expr) => { Some(self.analyze_expr(rc_mtree_expr.deref(), frame.clone())?) }
+            None => { None }
+        };
 
-        // LET ID ASSIGN VAL SEMICOLON
-        if mtree_let.children.len() > 1 {
-            let rc_mtree_assign = mtree_let.children.get(1).unwrap();
-            let rc_mtree_assign_ = self.analyze_assign(rc_mtree_assign, frame);
+        // new symbol in frame of block (shadows symbol of same name)
+        let TCode::ID(name) = &mtree_id.token.code else {
+            return Err(Error::semantic("Expect identifier after let!")
+                .at(mtree_id.token.loc.clone()));
+        };
+        // only const is immutable (parser ensures that it is initialized)
+        let mutable = mtree_let.token.code != TCode::CONST;
+        let mut symbol = ASymbol::new(name.clone(), self.decl_typ.get());
+        symbol.mutable = mutable;
+        symbol.pos = mtree_id.token.loc.first.clone();
+        let loc = frame.borrow_mut().symbol_new(symbol);
+        let token_ref = Token {
+            code: TCode::A_REF(loc),
+            loc: mtree_id.token.loc.clone(),
+        };
 
-            Rc::new(MTree {
-                token: mtree_let.token.clone(),
-                children: vec![rc_mtree_id.clone(), rc_mtree_assign_],
-            })
+        let mut mtree_let_ = MTree::new(mtree_let.token.clone());
+        mtree_let_.children.push(Rc::new(MTree::new(token_ref)));
+        if let Some(rc_expr_) = option_expr_ {
+            mtree_let_.children.push(rc_expr_);
         }
-        // LET ID SEMICOLON
-        else {
-            Rc::new(MTree {
-                token: mtree_let.token.clone(),
-                children: vec![rc_mtree_id.clone()],
-            })
+        Ok(Rc::new(mtree_let_))
+    }
+
+    /// error for assignment to immutable symbol, pointing at its declaration
+    fn check_mutable(&self, symbol: &ASymbol, mtree_id: &MTree) -> Result<()> {
+        if symbol.mutable {
+            return Ok(());
         }
+        Err(Error::semantic(format!("Can't assign twice to immutable variable '{}'!", symbol.name))
+            .at(mtree_id.token.loc.clone())
+            .with_label(symbol.pos.clone(), "declared immutable here"))
     }
 
-    pub fn analyze_expr(&self, mtree_expr: &MTree, frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
+    pub fn analyze_expr(&self, mtree_expr: &MTree, frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
         let code = &mtree_expr.token.code;
         match code {
             TCode::ID(_name) => {
                 self.analyze_id_load(mtree_expr, frame)
             }
             TCode::VAL(_val) => {
-                Rc::new(mtree_expr.clone())
+                Ok(Rc::new(mtree_expr.clone()))
             }
             TCode::CALL => {
                 self.analyze_call(mtree_expr, frame)
@@ -263,62 +387,148 @@ impl Analyzer {
                 if code.isLRAOp() {
                     self.analyze_LRAOp(mtree_expr, frame)
                 } else {
-                    panic!("Code {:?}", code)
+                    Err(Error::semantic(format!("Invalid expression {:?}", code))
+                        .at(mtree_expr.token.loc.clone()))
                 }
             }
         }
     }
 
-    pub fn analyze_id_load(&self, mtree_id: &MTree, frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
+    pub fn analyze_id_load(&self, mtree_id: &MTree, frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
         match &mtree_id.token.code {
             TCode::ID(name) => {
                 // create reference into frame
                 let symbol = match frame.borrow().symbol_lookup(name) {
                     Some(symbol) => { symbol }
-                    None => { panic!("\nERROR: Variable {} used before declaration!\n", name); }
+                    None => {
+                        return Err(Error::semantic(format!("Variable {} used before declaration!", name))
+                            .at(mtree_id.token.loc.clone()));
+                    }
                 };
-                Rc::new(MTree::new( Token {
+                Ok(Rc::new(MTree::new( Token {
                     code: TCode::A_REF(symbol.loc.clone()),
                     loc: mtree_id.token.loc.clone(),
-                }))
+                })))
             }
             _ => {
-                panic!("Expected ID but got {:?}", mtree_id.token.code)
+                Err(Error::semantic(format!("Expected ID but got {:?}", mtree_id.token.code))
+                    .at(mtree_id.token.loc.clone()))
             }
         }
     }
 
-    pub fn analyze_call(&self, mtree_call: &MTree, frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
+    pub fn analyze_call(&self, mtree_call: &MTree, frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
+
+        // callee with known function type must take the number of arguments given,
+        // dynamic callee (such as function passed as parameter) is checked at runtime
+        let mtree_id = mtree_call.children.first().unwrap().deref();
+        if let TCode::ID(name) = &mtree_id.token.code
+            && let Some(symbol) = frame.borrow().symbol_lookup(name)
+        {
+            let n_args = mtree_call.children.len() - 1;
+            match symbol.typ.arity() {
+                None if matches!(symbol.typ, Typ::D) => {}
+                None => {
+                    return Err(Error::semantic(format!("'{}' is not a function!", name))
+                        .at(mtree_id.token.loc.clone())
+                        .with_label(symbol.pos.clone(), "declared here"));
+                }
+                Some(n_params) if n_params != n_args => {
+                    return Err(Error::semantic(format!(
+                        "Function '{}' {}!", name, arity_mismatch(n_params, n_args)))
+                        .at(mtree_id.token.loc.clone())
+                        .with_label(symbol.pos.clone(), "function defined here"));
+                }
+                _ => {}
+            }
+        }
 
         let mut mtree_call_ = MTree::new(mtree_call.token.clone());
         for rc_arg_mtree in &mtree_call.children {
             let mtree_arg = rc_arg_mtree.deref();
-            let rc_mtree_arg_ = self.analyze_expr(mtree_arg, frame.clone());
+            let rc_mtree_arg_ = self.analyze_expr(mtree_arg, frame.clone())?;
             mtree_call_.children.push(rc_mtree_arg_);
         }
 
-        Rc::new(mtree_call_)
+        Ok(Rc::new(mtree_call_))
     }
 
-    pub fn analyze_LRAOp(&self, mtree_expr: &MTree, frame: Rc<RefCell<AFrame>>) -> Rc<MTree> {
+    pub fn analyze_LRAOp(&self, mtree_expr: &MTree, frame: Rc<RefCell<AFrame>>) -> Result<Rc<MTree>> {
         let code = &mtree_expr.token.code;
         if mtree_expr.children.len() == 1 {
             let rc_arg = mtree_expr.children.get(0).unwrap();
-            let rc_arg_ = self.analyze_expr(rc_arg.deref(), frame);
-            let mut mtree_ = MTree::new(Token::from(code.clone()));
+            let rc_arg_ = self.analyze_expr(rc_arg.deref(), frame)?;
+            let mut mtree_ = MTree::new(mtree_expr.token.clone());
             mtree_.children.push(rc_arg_);
-            Rc::new(mtree_)
+            Ok(Rc::new(mtree_))
         } else if mtree_expr.children.len() == 2 {
             let rc_arg0 = mtree_expr.children.get(0).unwrap();
             let rc_arg1 = mtree_expr.children.get(1).unwrap();
-            let rc_arg0_ = self.analyze_expr(rc_arg0.deref(), frame.clone());
-            let rc_arg1_ = self.analyze_expr(rc_arg1.deref(), frame.clone());
-            let mut mtree_ = MTree::new(Token::from(code.clone()));
+            let rc_arg0_ = self.analyze_expr(rc_arg0.deref(), frame.clone())?;
+            let rc_arg1_ = self.analyze_expr(rc_arg1.deref(), frame.clone())?;
+            let mut mtree_ = MTree::new(mtree_expr.token.clone());
             mtree_.children.push(rc_arg0_);
             mtree_.children.push(rc_arg1_);
-            Rc::new(mtree_)
+            Ok(Rc::new(mtree_))
         } else {
-            panic!()
+            Err(Error::semantic(format!(
+                "Operator {:?} expects 1 or 2 operands, got {}", code, mtree_expr.children.len()))
+                .at(mtree_expr.token.loc.clone()))
         }
     }
+}
+
+
+/// description of call with wrong number of arguments
+pub fn arity_mismatch(n_params: usize, n_args: usize) -> String {
+    format!("takes {} argument(s) but {} {} given", n_params, n_args, if n_args == 1 { "was" } else { "were" })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::hw_assignment_3::Lexer;
+    use crate::hw_assignment_4::Parser;
+    use super::Analyzer;
+
+    /// message of analysis error (or None)
+    fn semantic_error(source: &str) -> Option<String> {
+        let Ok(lexer) = source.parse::<Lexer>();
+        let mut parser = Parser::new(lexer);
+        let tree = parser.analyze().unwrap();
+        Analyzer::new().analyze_global(Rc::new(tree)).err().map(|error| error.msg)
+    }
+
+    #[test]
+    fn call_with_wrong_number_of_arguments_is_error() {
+        let error = semantic_error("func f(a, b) [ return a; ] func main() [ f(1); ]");
+        assert_eq!(error.as_deref(), Some("Function 'f' takes 2 argument(s) but 1 was given!"));
+        let error = semantic_error("func f(a) [ return a; ] func main() [ f(1, 2); ]");
+        assert_eq!(error.as_deref(), Some("Function 'f' takes 1 argument(s) but 2 were given!"));
+    }
+
+    #[test]
+    fn call_of_parameter_is_checked_at_runtime() {
+        assert_eq!(semantic_error("func twice(f, x) [ return f(f(x)); ] func main() [ ]"), None);
+    }
+
+    #[test]
+    fn let_and_let_mut_can_be_reassigned() {
+        assert_eq!(semantic_error("func main() [ let p; p = 3; let q = 1; q = 2; read q; ]"), None);
+        assert_eq!(semantic_error("func main() [ let mut r; r = 1; read r; ]"), None);
+    }
+
+    #[test]
+    fn const_can_not_be_reassigned() {
+        let msg = Some("Can't assign twice to immutable variable 'c'!");
+        assert_eq!(semantic_error("func main() [ const c = 1; c = 2; ]").as_deref(), msg);
+        assert_eq!(semantic_error("func main() [ const c = 1; read c; ]").as_deref(), msg);
+        assert_eq!(semantic_error("const c = 1; func main() [ c = 2; ]").as_deref(), msg);
+    }
+
+    #[test]
+    fn uninitialized_let_can_be_used() {
+        assert_eq!(semantic_error("func main() [ let p; write p; let mut q; write q; ]"), None);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,270 @@
+use std::fmt;
+use crate::error::{Error, ErrorKind};
+use crate::token::TPos;
+
+
+/// severity of a diagnostic
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Level {
+    ERROR,
+}
+
+
+/// secondary label pointing at related source code
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub pos: TPos,      // position of related source code
+    pub msg: String,    // text shown next to the underline
+}
+
+
+/// message about source code
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub level: Level,           // severity
+    pub code: Option<String>,   // short category, such as "syntax"
+    pub msg: String,            // description
+    pub pos: TPos,              // primary position (or empty)
+    pub labels: Vec<Label>,     // secondary positions
+}
+
+
+/// source code used to render snippets
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub name: String,   // file name
+    pub text: String,   // content
+}
+
+
+/// collects diagnostics of all stages and renders them against the source code
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    pub source: Source,
+    pub items: Vec<Diagnostic>,
+}
+
+
+impl Label {
+
+    pub fn new(pos: TPos, msg: impl Into<String>) -> Label {
+        Label { pos, msg: msg.into() }
+    }
+}
+
+
+impl Diagnostic {
+
+    pub fn new(level: Level, msg: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            level,
+            code: None,
+            msg: msg.into(),
+            pos: TPos::new(0, 0, 0),
+            labels: vec![],
+        }
+    }
+
+    pub fn error(msg: impl Into<String>) -> Diagnostic {
+        Self::new(Level::ERROR, msg)
+    }
+
+    pub fn at(mut self, pos: TPos) -> Diagnostic {
+        self.pos = pos;
+        self
+    }
+
+
+    /// render diagnostic in the style of rustc:
+    ///
+    ///   error[syntax]: Expected SEMICOLON, got ID("write")
+    ///    --> example.txt:4:5
+    ///     |
+    ///   4 |     write x;
+    ///     |     ^^^^^
+    ///
+    pub fn render(&self, source: &Source) -> String {
+        let mut s = match &self.code {
+            Some(code) => { format!("{}[{}]: {}\n", self.level, code, self.msg) }
+            None => { format!("{}: {}\n", self.level, self.msg) }
+        };
+
+        // collect underlines (primary first), ordered by row
+        let mut marks: Vec<(&TPos, char, &str)> = vec![];
+        if !self.pos.is_empty() {
+            marks.push((&self.pos, '^', ""));
+        }
+        for label in &self.labels {
+            if !label.pos.is_empty() {
+                marks.push((&label.pos, '-', label.msg.as_str()));
+            }
+        }
+        if marks.is_empty() {
+            return format!("{} --> {}\n", s, source.name);
+        }
+        marks.sort_by_key(|(pos, _, _)| (pos.row, pos.col));
+
+        let pos_main = if self.pos.is_empty() { marks[0].0 } else { &self.pos };
+        let width = marks.iter().map(|(pos, _, _)| pos.row).max().unwrap_or(0).to_string().len();
+        s.push_str(&format!("{:w$}--> {}:{}:{}\n", "", source.name, pos_main.row, pos_main.col, w = width));
+        s.push_str(&format!("{:w$} |\n", "", w = width));
+
+        let lines: Vec<&str> = source.text.lines().collect();
+        let mut row_last = 0;
+        for (pos, marker, msg) in marks {
+            if row_last != 0 && pos.row > row_last + 1 {
+                s.push_str("...\n");
+            }
+            let line = lines.get(pos.row - 1).copied().unwrap_or("");
+            if pos.row != row_last {
+                s.push_str(&format!("{:>w$} | {}\n", pos.row, line, w = width));
+            }
+            // keep tabs so that underline lines up with source line
+            let padding: String = line.chars().take(pos.col - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let underline = marker.to_string().repeat(pos.len.max(1));
+            s.push_str(format!("{:w$} | {}{} {}\n", "", padding, underline, msg, w = width).trim_end());
+            s.push('\n');
+            row_last = pos.row;
+        }
+        s
+    }
+}
+
+
+impl From<&Error> for Diagnostic {
+    fn from(error: &Error) -> Diagnostic {
+        let code = match error.kind {
+            ErrorKind::IO => { "io" }
+            ErrorKind::LEX => { "lex" }
+            ErrorKind::PARSE => { "syntax" }
+            ErrorKind::SEMANTIC => { "semantic" }
+            ErrorKind::RUNTIME => { "runtime" }
+        };
+        let mut diagnostic = Diagnostic::error(error.msg.clone()).at(error.loc.first.clone());
+        diagnostic.code = Some(String::from(code));
+        diagnostic.labels = error.labels.clone();
+        diagnostic
+    }
+}
+
+
+impl Source {
+
+    pub fn new(name: impl Into<String>, text: impl Into<String>) -> Source {
+        Source { name: name.into(), text: text.into() }
+    }
+}
+
+
+impl Diagnostics {
+
+    pub fn new(source: Source) -> Diagnostics {
+        Diagnostics { source, items: vec![] }
+    }
+
+    pub fn report(&mut self, diagnostic: Diagnostic) {
+        self.items.push(diagnostic);
+    }
+
+    pub fn report_error(&mut self, error: &Error) {
+        self.report(Diagnostic::from(error));
+    }
+
+    /// print all diagnostics collected so far (and forget them)
+    pub fn flush(&mut self) {
+        for diagnostic in &self.items {
+            println!("{}", diagnostic.render(&self.source));
+        }
+        self.items.clear();
+    }
+}
+
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Level::ERROR => write!(f, "error"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::token::{TLoc, TPos};
+    use super::{Diagnostic, Diagnostics, Source};
+
+    const TEXT: &str = "func main()\n[\n    let x = (1 + 2;\n    write x;\n]";
+
+    fn render(error: &Error) -> String {
+        Diagnostic::from(error).render(&Source::new("test.txt", TEXT))
+    }
+
+    fn loc(row: usize, col: usize, len: usize) -> TLoc {
+        TLoc { first: TPos::new(row, col, len), last: TPos::new(0, 0, 0) }
+    }
+
+    #[test]
+    fn render_single_label() {
+        let error = Error::semantic("Variable y used before declaration!").at(loc(4, 11, 1));
+        assert_eq!(render(&error), "\
+error[semantic]: Variable y used before declaration!
+ --> test.txt:4:11
+  |
+4 |     write x;
+  |           ^
+");
+    }
+
+    #[test]
+    fn render_secondary_label_on_same_line() {
+        let error = Error::parse("Expected PAREN_R, got SEMICOLON")
+            .at(loc(3, 19, 1))
+            .with_label(TPos::new(3, 13, 1), "parenthesis opened here");
+        assert_eq!(render(&error), "\
+error[syntax]: Expected PAREN_R, got SEMICOLON
+ --> test.txt:3:19
+  |
+3 |     let x = (1 + 2;
+  |             - parenthesis opened here
+  |                   ^
+");
+    }
+
+    #[test]
+    fn render_label_on_other_line() {
+        let error = Error::parse("Expected BRACE_R, got EOI")
+            .at(loc(5, 2, 0))
+            .with_label(TPos::new(2, 1, 1), "block opened here");
+        assert_eq!(render(&error), "\
+error[syntax]: Expected BRACE_R, got EOI
+ --> test.txt:5:2
+  |
+2 | [
+  | - block opened here
+...
+5 | ]
+  |  ^
+");
+    }
+
+    #[test]
+    fn render_without_position() {
+        let error = Error::io("Can't open file");
+        assert_eq!(render(&error), "error[io]: Can't open file\n --> test.txt\n");
+    }
+
+    #[test]
+    fn report_collects_diagnostics_until_flush() {
+        let mut diagnostics = Diagnostics::new(Source::new("test.txt", TEXT));
+        diagnostics.report_error(&Error::lex("Invalid character '$'").at(loc(1, 1, 1)));
+        diagnostics.report_error(&Error::runtime("Division by zero!"));
+        assert_eq!(diagnostics.items.len(), 2);
+        assert_eq!(diagnostics.items[0].code.as_deref(), Some("lex"));
+        diagnostics.flush();
+        assert!(diagnostics.items.is_empty());
+    }
+}
\ No newline at end of file
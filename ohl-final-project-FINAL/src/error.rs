@@ -0,0 +1,162 @@
+use std::fmt;
+use crate::diagnostic::Label;
+use crate::token::{TLoc, TPos};
+
+
+/// stage in which an error was detected
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    IO,         // source file can't be accessed
+    LEX,        // invalid character or literal
+    PARSE,      // syntax error
+    SEMANTIC,   // error found by analyzer (such as undeclared variable)
+    RUNTIME,    // error during evaluation (such as invalid operand)
+}
+
+
+/// recoverable error of lexer, parser, analyzer or evaluator
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub kind: ErrorKind,    // stage which detected the error
+    pub msg: String,        // description of the error
+    pub loc: TLoc,          // location of the error in source code (or empty)
+    pub labels: Vec<Label>, // related locations in source code
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+impl Error {
+
+    pub fn new(kind: ErrorKind, msg: impl Into<String>) -> Error {
+        Error { kind, msg: msg.into(), loc: TLoc::empty(), labels: vec![] }
+    }
+
+    pub fn io(msg: impl Into<String>) -> Error {
+        Self::new(ErrorKind::IO, msg)
+    }
+
+    pub fn lex(msg: impl Into<String>) -> Error {
+        Self::new(ErrorKind::LEX, msg)
+    }
+
+    pub fn parse(msg: impl Into<String>) -> Error {
+        Self::new(ErrorKind::PARSE, msg)
+    }
+
+    pub fn semantic(msg: impl Into<String>) -> Error {
+        Self::new(ErrorKind::SEMANTIC, msg)
+    }
+
+    pub fn runtime(msg: impl Into<String>) -> Error {
+        Self::new(ErrorKind::RUNTIME, msg)
+    }
+
+    /// set location of error in source code
+    pub fn at(mut self, loc: TLoc) -> Error {
+        self.loc = loc;
+        self
+    }
+
+    /// point at related source code (such as the opening delimiter)
+    pub fn with_label(mut self, pos: TPos, msg: impl Into<String>) -> Error {
+        self.labels.push(Label::new(pos, msg));
+        self
+    }
+
+    /// set location of error in source code, unless already known
+    pub fn at_default(self, loc: &TLoc) -> Error {
+        if self.loc.is_empty() { self.at(loc.clone()) } else { self }
+    }
+}
+
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::IO => write!(f, "I/O error"),
+            ErrorKind::LEX => write!(f, "lexical error"),
+            ErrorKind::PARSE => write!(f, "syntax error"),
+            ErrorKind::SEMANTIC => write!(f, "semantic error"),
+            ErrorKind::RUNTIME => write!(f, "runtime error"),
+        }
+    }
+}
+
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.loc.is_empty() {
+            write!(f, "{}: {}", self.kind, self.msg)
+        } else {
+            write!(f, "{} at {}:{}: {}", self.kind, self.loc.first.row, self.loc.first.col, self.msg)
+        }
+    }
+}
+
+
+impl std::error::Error for Error { }
+
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::analyzer::Analyzer;
+    use crate::evaluator::Evaluator;
+    use crate::hw_assignment_3::Lexer;
+    use crate::hw_assignment_4::Parser;
+    use super::{Error, ErrorKind};
+
+    /// first error of any stage (lexer, parser, analyzer or evaluator) of program
+    fn first_error(source: &str) -> Error {
+        let Ok(lexer) = source.parse::<Lexer>();
+        let mut parser = Parser::new(lexer);
+        let tree = parser.analyze().unwrap();
+        if let Some(error) = parser.errors.first() {
+            return error.clone();
+        }
+        let tree_analyzed = Analyzer::new().analyze_global(Rc::new(tree)).unwrap();
+        let mut evaluator = Evaluator::new();
+        evaluator.set_output(std::io::sink());
+        evaluator.evaluate(&tree_analyzed).unwrap_err()
+    }
+
+    fn kind_and_position(error: &Error) -> (ErrorKind, usize, usize) {
+        (error.kind, error.loc.first.row, error.loc.first.col)
+    }
+
+    #[test]
+    fn lex_error_has_kind_and_position() {
+        let error = first_error("func main()\n[\n    write 1 # 2;\n]");
+        assert_eq!(kind_and_position(&error), (ErrorKind::LEX, 3, 13));
+        assert_eq!(error.to_string(), "lexical error at 3:13: Invalid character '#'");
+    }
+
+    #[test]
+    fn parse_error_has_kind_and_position() {
+        let error = first_error("func main()\n[\n    write 1\n    write 2;\n]");
+        assert_eq!(kind_and_position(&error), (ErrorKind::PARSE, 4, 5));
+    }
+
+    #[test]
+    fn runtime_error_has_kind_and_position() {
+        let error = first_error("func main()\n[\n    let s = \"a\";\n    write s == 1;\n]");
+        assert_eq!(kind_and_position(&error), (ErrorKind::RUNTIME, 4, 13));
+        assert_eq!(error.msg, "Both types need to be numeric for operator EQ, got (STR, I64)!");
+    }
+
+    #[test]
+    fn runtime_error_names_value() {
+        let error = first_error("func f(g) [ return g(); ] func main() [ write f(\"x\"); ]");
+        assert_eq!(kind_and_position(&error), (ErrorKind::RUNTIME, 1, 20));
+        assert_eq!(error.msg, "Expected function, got \"x\"!");
+    }
+
+    #[test]
+    fn error_without_position() {
+        let error = Error::io("Can't open file");
+        assert!(error.loc.is_empty());
+        assert_eq!(error.to_string(), "I/O error: Can't open file");
+    }
+}
\ No newline at end of file
@@ -1,26 +1,95 @@
 use std::cell::RefCell;
+use std::io::{self, BufRead, Write};
 use std::ops::{Deref};
 use std::rc::Rc;
+use crate::analyzer::arity_mismatch;
+use crate::error::{Error, Result};
 use crate::frame_analyze::{FrameTyp};
 use crate::frame_call::CFrame;
 use crate::log::Log;
 use crate::mtree::MTree;
 use crate::token::{TCode};
-use crate::value::{DValue};
+use crate::value::{DValue, Overflow};
 
 
 // control flow
 pub enum Control {
     NEXT,
     RETURN,
-    _BREAK,
-    _CONTINUE,
+    BREAK,
+    CONTINUE,
+}
+
+
+/// source of program input ('read')
+pub enum Input {
+    Stdin,                      // stdin, shared with the command line
+    Reader(Box<dyn BufRead>),   // any reader (file, in-memory, ...)
+}
+
+
+impl Input {
+
+    fn read_line(&mut self, line: &mut String) -> io::Result<usize> {
+        match self {
+            // no buffer of its own, so lines not read here are left for the command line
+            Input::Stdin => { io::stdin().lock().read_line(line) }
+            Input::Reader(reader) => { reader.read_line(line) }
+        }
+    }
+}
+
+
+/// destination of program output ('write')
+pub enum Output {
+    Writer(Box<dyn Write>),     // any writer (stdout, file, ...)
+    #[cfg(test)]
+    Buffer(Vec<u8>),            // in-memory buffer (for tests)
+}
+
+
+impl Output {
+
+    pub fn stdout() -> Output {
+        Output::Writer(Box::new(io::stdout()))
+    }
+
+    #[cfg(test)]
+    pub fn buffer() -> Output {
+        Output::Buffer(vec![])
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        match self {
+            Output::Writer(writer) => {
+                writeln!(writer, "{}", line)?;
+                writer.flush()
+            }
+            #[cfg(test)]
+            Output::Buffer(buffer) => {
+                writeln!(buffer, "{}", line)
+            }
+        }
+    }
+
+    /// content written so far (only for Output::Buffer)
+    #[cfg(test)]
+    pub fn buffered(&self) -> Option<String> {
+        match self {
+            Output::Writer(_) => { None }
+            Output::Buffer(buffer) => { Some(String::from_utf8_lossy(buffer).into_owned()) }
+        }
+    }
 }
 
 
 /// evaluate an analyzed MTree
 pub struct Evaluator {
     pub log: Log,
+    input: Input,               // source of values for 'read'
+    pub output: Output,         // destination of values for 'write'
+    pub write_prefix: bool,     // prefix written values with "> " ?
+    pub overflow: Overflow,     // behavior of I64 arithmetic out of range
 }
 
 
@@ -29,41 +98,83 @@ impl Evaluator {
     pub fn new() -> Evaluator {
         Evaluator {
             log: Log::new(),
+            input: Input::Stdin,
+            output: Output::stdout(),
+            write_prefix: true,
+            overflow: Overflow::ERROR,
         }
     }
 
 
-    pub fn evaluate(&mut self, mtree_block: &MTree) {
+    /// replace the source of values for 'read' (stdin by default)
+    pub fn set_input<R: BufRead + 'static>(&mut self, reader: R) {
+        self.input = Input::Reader(Box::new(reader));
+    }
+
+
+    /// replace the destination of values for 'write' (stdout by default)
+    pub fn set_output<W: Write + 'static>(&mut self, writer: W) {
+        self.output = Output::Writer(Box::new(writer));
+    }
+
+
+    /// collect values for 'write' in memory, see take_output()
+    #[cfg(test)]
+    pub fn set_output_buffer(&mut self) {
+        self.output = Output::buffer();
+    }
+
+
+    /// content of in-memory output buffer (buffer is cleared)
+    #[cfg(test)]
+    pub fn take_output(&mut self) -> Option<String> {
+        let content = self.output.buffered();
+        if let Output::Buffer(buffer) = &mut self.output {
+            buffer.clear();
+        }
+        content
+    }
+
+
+    pub fn evaluate(&mut self, mtree_block: &MTree) -> Result<()> {
         self.log.show_debug = false;
-        self.evaluate_block(mtree_block, None);
+        self.evaluate_block(mtree_block, None)?;
+        Ok(())
     }
 
 
     pub fn evaluate_block(
         &mut self, mtree_block: &MTree, option_frame_up: Option<Rc<RefCell<CFrame>>>)
-        -> (DValue, Control)
+        -> Result<(DValue, Control)>
+    {
+        // evaluate block with new INNER BLOCK CFrame
+        let rcc_frame_block = self.block_frame(mtree_block, option_frame_up)?;
+        self.evaluate_block_framed(mtree_block, rcc_frame_block)
+    }
+
+
+    /// create new dynamic frame (CFrame) for block
+    fn block_frame(
+        &mut self, mtree_block: &MTree, option_frame_up: Option<Rc<RefCell<CFrame>>>)
+        -> Result<Rc<RefCell<CFrame>>>
     {
         // get block's AFrame
         let rcc_aFrame = match &mtree_block.token.code {
             TCode::A_BLOCK(rcc_aFrame) => { rcc_aFrame }
-            _ => {  panic!("Expected Code::META_BLOCK tree!") }
+            _ => { return Err(Error::runtime("Expected Code::META_BLOCK tree!")) }
         };
 
-        // create new dynamic block frame (CFrame)
         let size_cFrame = rcc_aFrame.borrow().size_symbols(FrameTyp::FUNCTION);
         let mut cFrame = CFrame::new(size_cFrame);
         cFrame.aFrame = Some(rcc_aFrame.clone()); // link with AFrame
         cFrame.cFrame_up = option_frame_up; // link with OUTER CFrame
-
-        // evaluate block with already created INNER BLOCK CFrame
-        let rcc_frame_block = Rc::new(RefCell::new(cFrame));
-        self.evaluate_block_framed(mtree_block, rcc_frame_block)
+        Ok(Rc::new(RefCell::new(cFrame)))
     }
 
 
     pub fn evaluate_block_framed(
         &mut self, mtree_block: &MTree, rcc_frame_block: Rc<RefCell<CFrame>>)
-        -> (DValue, Control)
+        -> Result<(DValue, Control)>
     {
         self.log.debug("evaluate2_block()");
         self.log.indent_inc();
@@ -72,23 +183,23 @@ impl Evaluator {
         // evaluate all statements in block
         for child in mtree_block.children.iter() {
             let mtree_stmt = child.deref();
-            ret = self.evaluate_stmt(mtree_stmt, rcc_frame_block.clone());
+            ret = self.evaluate_stmt(mtree_stmt, rcc_frame_block.clone())?;
             match ret.1 {
                 Control::NEXT => { continue; }
-                Control::_BREAK => { ret.1 = Control::NEXT; break; }
+                // leave block, loop (or function) handles control
                 _ => { break; }
             }
         }
 
         self.log.indent_dec();
-        ret
+        Ok(ret)
     }
 
 
     pub fn evaluate_stmt(&mut self, mtree_stmt: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
-                         -> (DValue, Control)
+                         -> Result<(DValue, Control)>
     {
-        match &mtree_stmt.token.code {
+        let result = match &mtree_stmt.token.code {
             TCode::RETURN => {
                 self.evaluate_return(mtree_stmt, rcc_frame)
             }
@@ -98,121 +209,325 @@ impl Evaluator {
             TCode::WHILE => {
                 self.evaluate_while(mtree_stmt, rcc_frame)
             }
+            TCode::FOR => {
+                self.evaluate_for(mtree_stmt, rcc_frame)
+            }
+            TCode::LET | TCode::MUT | TCode::CONST => {
+                self.evaluate_let(mtree_stmt, rcc_frame).map(|value| (value, Control::NEXT))
+            }
+            TCode::BREAK => {
+                Ok((DValue::TOK, Control::BREAK))
+            }
+            TCode::CONTINUE => {
+                Ok((DValue::TOK, Control::CONTINUE))
+            }
             TCode::READ => {
-                (self.evaluate_read(mtree_stmt, rcc_frame), Control::NEXT)
+                self.evaluate_read(mtree_stmt, rcc_frame).map(|value| (value, Control::NEXT))
             }
             TCode::WRITE => {
-                (self.evaluate_write(mtree_stmt, rcc_frame), Control::NEXT)
+                self.evaluate_write(mtree_stmt, rcc_frame).map(|value| (value, Control::NEXT))
             }
             TCode::FUNC => {
-                (DValue::TOK, Control::NEXT)
+                Ok((DValue::TOK, Control::NEXT))
             }
             _ => {
                 // assume tree is an expression
-                (self.evaluate_expr(mtree_stmt, rcc_frame), Control::NEXT)
+                self.evaluate_expr(mtree_stmt, rcc_frame).map(|value| (value, Control::NEXT))
             }
-        }
+        };
+        result.map_err(|e| e.at_default(&mtree_stmt.token.loc))
     }
 
 
     pub fn evaluate_return(
         &mut self, mtree_return: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
-        -> (DValue, Control)
+        -> Result<(DValue, Control)>
     {
         self.log.debug("evaluate_return()");
         self.log.indent_inc();
-        let mtree_expr = mtree_return.children.get(0).unwrap().deref();
-        let value = self.evaluate_expr(mtree_expr, rcc_frame);
+        // bare 'return;' returns TOK
+        let value = match mtree_return.children.first() {
+            Some(rc_expr) => { self.evaluate_expr(rc_expr.deref(), rcc_frame)? }
+            None => { DValue::TOK }
+        };
         self.log.debug(format!("value={:?}", value).as_str());
         self.log.indent_dec();
-        (value, Control::RETURN)
+        Ok((value, Control::RETURN))
     }
 
 
     pub fn evaluate_if(
         &mut self, mtree_if: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
-        -> (DValue, Control)
+        -> Result<(DValue, Control)>
     {
         self.log.debug("evaluate_if()");
         self.log.indent_inc();
-        let cond = mtree_if.children.get(0).unwrap().deref();
-        let value_cond = self.evaluate_expr(cond, rcc_frame.clone());
-        let ret_block = if let DValue::BOOL(b) = value_cond {
-            let idx_branch = if b { 1 } else { 2 };
-            let mtree_branch = mtree_if.children.get(idx_branch).unwrap().deref();
-            self.evaluate_block(mtree_branch, Some(rcc_frame.clone()))
-        } else {
-            panic!("Condition must result in value of type Bool!");
-        };
+
+        // first branch with true condition (or else block, if any) is evaluated
+        let mut ret_block = (DValue::TOK, Control::NEXT);
+        for branch in mtree_if.children.chunks(2) {
+            let mtree_branch = match branch {
+                [rc_cond, rc_block] => {
+                    let cond = rc_cond.deref();
+                    match self.evaluate_expr(cond, rcc_frame.clone())? {
+                        DValue::BOOL(true) => { rc_block.deref() }
+                        DValue::BOOL(false) => { continue; }
+                        _ => {
+                            return Err(Error::runtime("Condition must result in value of type Bool!")
+                                .at(cond.token.loc.clone()));
+                        }
+                    }
+                }
+                [rc_else] => { rc_else.deref() }
+                _ => { break; }
+            };
+            ret_block = self.evaluate_block(mtree_branch, Some(rcc_frame.clone()))?;
+            break;
+        }
         self.log.indent_dec();
-        ret_block
+        Ok(ret_block)
     }
 
     pub fn evaluate_while(
         &mut self, _mtree_while: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
-        -> (DValue, Control)
+        -> Result<(DValue, Control)>
     {
         self.log.debug("evaluate_while()");
         self.log.indent_inc();
 
         let condition = _mtree_while.children.get(0).unwrap().deref();
-        let mut value_condition = self.evaluate_expr(condition, rcc_frame.clone());
+        let mut value_condition = self.evaluate_expr(condition, rcc_frame.clone())?;
         let mtree_branch = _mtree_while.children.get(1).unwrap().deref();
 
-        fn match_value_condition(va_co: DValue) -> bool {
+        fn match_value_condition(va_co: DValue, condition: &MTree) -> Result<bool> {
             match va_co {
-                DValue::BOOL(true) => { true }
-                DValue::BOOL(false) => { false }
+                DValue::BOOL(b) => { Ok(b) }
                 _ => {
-                    panic!("conditional statement returned non-boolean value in evaluate_while() in evaluator.rs")
+                    Err(Error::runtime("Condition of while must result in value of type Bool!")
+                        .at(condition.token.loc.clone()))
                 }
             }
         }
 
-        let mut loop_condition: bool = match_value_condition(value_condition.clone());
-        let mut ret_block: (DValue, Control) = (DValue::BOOL(false), Control::_CONTINUE);
+        let mut loop_condition: bool = match_value_condition(value_condition, condition)?;
 
         while loop_condition {
-            ret_block = if let DValue::BOOL(b) = value_condition {
-                self.evaluate_block(mtree_branch, Some(rcc_frame.clone()))
-            } else {
-                panic!("Condition must result in value of type Bool!");
-            };
+            let ret_block = self.evaluate_block(mtree_branch, Some(rcc_frame.clone()))?;
+            match ret_block.1 {
+                Control::NEXT | Control::CONTINUE => {}
+                Control::BREAK => { break; }
+                Control::RETURN => {
+                    self.log.indent_dec();
+                    return Ok(ret_block);
+                }
+            }
+
+            value_condition = self.evaluate_expr(condition, rcc_frame.clone())?;
+            loop_condition = match_value_condition(value_condition, condition)?;
+        }
+
+        self.log.indent_dec();
+        Ok((DValue::TOK, Control::NEXT))
+    }
+
+
+    pub fn evaluate_for(
+        &mut self, mtree_for: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
+        -> Result<(DValue, Control)>
+    {
+        self.log.debug("evaluate_for()");
+        self.log.indent_inc();
+
+        let mtree_ref = mtree_for.children.first().unwrap().deref();
+        let mtree_iterable = mtree_for.children.get(1).unwrap().deref();
+        let mtree_body = mtree_for.children.get(2).unwrap().deref();
+        let loc = match &mtree_ref.token.code {
+            TCode::A_REF(loc) => { *loc }
+            _ => { return Err(Error::runtime("Variable of for loop must be REF!")) }
+        };
+
+        let values = self.evaluate_iterable(mtree_iterable, rcc_frame.clone())?;
+        for value in values {
+            // every iteration gets a new frame with loop variable
+            let rcc_frame_body = self.block_frame(mtree_body, Some(rcc_frame.clone()))?;
+            rcc_frame_body.borrow_mut().value_store(&loc, value)?;
+            let ret_block = self.evaluate_block_framed(mtree_body, rcc_frame_body)?;
+            match ret_block.1 {
+                Control::NEXT | Control::CONTINUE => {}
+                Control::BREAK => { break; }
+                Control::RETURN => {
+                    self.log.indent_dec();
+                    return Ok(ret_block);
+                }
+            }
+        }
+
+        self.log.indent_dec();
+        Ok((DValue::TOK, Control::NEXT))
+    }
+
 
-            value_condition = self.evaluate_expr(condition, rcc_frame.clone());
-            loop_condition = match_value_condition(value_condition.clone());
+    /// values of range (I64 from start up to end, with optional step) or of iterable value (STR)
+    fn evaluate_iterable(
+        &mut self, mtree_iterable: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
+        -> Result<Box<dyn Iterator<Item = DValue>>>
+    {
+        let inclusive = match mtree_iterable.token.code {
+            TCode::DOT_DOT => { false }
+            TCode::DOT_DOT_EQ => { true }
+            _ => {
+                let value = self.evaluate_expr(mtree_iterable, rcc_frame)?;
+                return match value {
+                    DValue::STR(s) => {
+                        let chars: Vec<char> = s.chars().collect();
+                        Ok(Box::new(chars.into_iter().map(DValue::CHAR)))
+                    }
+                    DValue::_LIST(values) => { Ok(Box::new(values.into_iter())) }
+                    _ => {
+                        Err(Error::runtime(format!("Can't iterate over value of type {}!", value.dynamic_typ()))
+                            .at(mtree_iterable.token.loc.clone()))
+                    }
+                };
+            }
+        };
+
+        // start, end and step of range
+        let mut bounds = vec![];
+        for rc_bound in &mtree_iterable.children {
+            match self.evaluate_expr(rc_bound.deref(), rcc_frame.clone())? {
+                DValue::I64(i) => { bounds.push(i); }
+                value => {
+                    return Err(Error::runtime(format!(
+                        "Range bounds must be of type I64, got {}!", value.dynamic_typ()))
+                        .at(rc_bound.token.loc.clone()));
+                }
+            }
         }
+        let (start, end) = (bounds[0], bounds[1]);
+        let step = bounds.get(2).copied().unwrap_or(1);
+        if step == 0 {
+            return Err(Error::runtime("Step of range must not be 0!")
+                .at(mtree_iterable.children[2].token.loc.clone()));
+        }
+
+        // count up (or down, for negative step) until end is reached
+        let values = std::iter::successors(Some(start), move |i| i.checked_add(step))
+            .take_while(move |&i| match (step > 0, inclusive) {
+                (true, false) => { i < end }
+                (true, true) => { i <= end }
+                (false, false) => { i > end }
+                (false, true) => { i >= end }
+            })
+            .map(DValue::I64);
+        Ok(Box::new(values))
+    }
+
+
+    pub fn evaluate_let(
+        &mut self, mtree_let: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
+        -> Result<DValue>
+    {
+        self.log.debug("evaluate_let()");
+        self.log.indent_inc();
+
+        let mtree_ref = mtree_let.children.first().unwrap().deref();
+        let loc = match &mtree_ref.token.code {
+            TCode::A_REF(loc) => { *loc }
+            _ => { return Err(Error::runtime("Variable of let must be REF!")) }
+        };
+
+        // uninitialized variable gets value TOK
+        let value = match mtree_let.children.get(1) {
+            Some(rc_expr) => { self.evaluate_expr(rc_expr.deref(), rcc_frame.clone())? }
+            None => { DValue::TOK }
+        };
+        rcc_frame.borrow_mut().value_init(&loc, value)
+            .map_err(|e| e.at_default(&mtree_ref.token.loc))?;
 
         self.log.indent_dec();
-        ret_block
+        Ok(DValue::TOK)
     }
 
 
     pub fn evaluate_read(
-        &mut self, _mtree_read: &MTree, _rcc_frame: Rc<RefCell<CFrame>>)
-        -> DValue
+        &mut self, mtree_read: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
+        -> Result<DValue>
     {
-        todo!()
+        self.log.debug("evaluate_read()");
+        self.log.indent_inc();
+
+        // get storage location
+        let mtree_ref = mtree_read.children.get(0).unwrap().deref();
+        let loc = match &mtree_ref.token.code {
+            TCode::A_REF(loc) => { *loc }
+            _ => { return Err(Error::runtime("Operand of read must be REF!")) }
+        };
+
+        // read one line of input
+        let mut line = String::new();
+        match self.input.read_line(&mut line) {
+            Ok(0) => { return Err(Error::runtime("Unexpected end of input!")) }
+            Ok(_) => {}
+            Err(e) => { return Err(Error::runtime(format!("Can't read input: {}", e))) }
+        }
+        let value = DValue::from_input(&line);
+
+        // store value
+        rcc_frame.borrow_mut().value_store(&loc, value.clone())
+            .map_err(|e| e.at_default(&mtree_ref.token.loc))?;
+        self.log.debug(format!("value={:?}", value).as_str());
+        self.log.indent_dec();
+        Ok(value)
     }
 
 
     pub fn evaluate_write(
         &mut self, mtree_write: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
-        -> DValue
+        -> Result<DValue>
     {
         self.log.debug("evaluate_print()");
         self.log.indent_inc();
         let mtree_expr = mtree_write.children.get(0).unwrap().deref();
-        let value = self.evaluate_expr(mtree_expr, rcc_frame);
-        print!("> {:}\n", value.toString());
+        let value = self.evaluate_expr(mtree_expr, rcc_frame)?;
+        let line = if self.write_prefix {
+            format!("> {:}", value.toString())
+        } else {
+            value.toString()
+        };
+        if let Err(e) = self.output.write_line(line.as_str()) {
+            return Err(Error::runtime(format!("Can't write output: {}", e)));
+        }
         self.log.indent_dec();
-        value
+        Ok(value)
+    }
+
+
+    /// short-circuit evaluation of AND and OR, right operand is evaluated only if needed
+    pub fn evaluate_logical(
+        &mut self, mtree_expr: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
+        -> Result<DValue>
+    {
+        let code = &mtree_expr.token.code;
+        let mtree_left = mtree_expr.children.first().unwrap().deref();
+        let mtree_right = mtree_expr.children.get(1).unwrap().deref();
+
+        let value_left = self.evaluate_expr(mtree_left, rcc_frame.clone())?;
+        let b_left = value_left.logicalOperand(code, "left")
+            .map_err(|e| e.at_default(&mtree_left.token.loc))?;
+        if (*code == TCode::AND && !b_left) || (*code == TCode::OR && b_left) {
+            return Ok(DValue::BOOL(b_left));
+        }
+
+        let value_right = self.evaluate_expr(mtree_right, rcc_frame)?;
+        let b_right = value_right.logicalOperand(code, "right")
+            .map_err(|e| e.at_default(&mtree_right.token.loc))?;
+        Ok(DValue::BOOL(b_right))
     }
 
 
     pub fn evaluate_expr(
         &mut self, mtree_expr: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
-        -> DValue
+        -> Result<DValue>
     {
         self.log.debug("evaluate_expr()");
         self.log.indent_inc();
@@ -221,7 +536,8 @@ impl Evaluator {
         let value = if let TCode::A_REF(loc) = &code {
 
             self.log.debug(format!("LOC {:?}", loc).as_str() );
-            let value = rcc_frame.borrow().value_load(loc);
+            let value = rcc_frame.borrow().value_load(loc)
+                .map_err(|e| e.at_default(&mtree_expr.token.loc))?;
             self.log.debug(format!("VAL {:?}", value).as_str() );
             value
 
@@ -231,7 +547,7 @@ impl Evaluator {
         }
         else if let TCode::CALL = code {
 
-            self.evaluate_call(mtree_expr, rcc_frame.clone())
+            self.evaluate_call(mtree_expr, rcc_frame.clone())?
 
         }
         else if code.isLRAOp() {
@@ -240,21 +556,29 @@ impl Evaluator {
 
                 let mtree_unary= mtree_expr.children.get(0).unwrap().deref();
                 let value_unary = self.evaluate_expr(
-                    mtree_unary, rcc_frame.clone());
-                value_unary.unaryOp(code)
+                    mtree_unary, rcc_frame.clone())?;
+                value_unary.unaryOp(code, self.overflow)
+                    .map_err(|e| e.at_default(&mtree_expr.token.loc))?
+
+            } else if mtree_expr.children.len() == 2 && matches!(code, TCode::AND | TCode::OR) {
+
+                self.evaluate_logical(mtree_expr, rcc_frame.clone())?
 
             } else if mtree_expr.children.len() == 2 {
 
                 let mtree_left= mtree_expr.children.get(0).unwrap().deref();
                 let mtree_right= mtree_expr.children.get(1).unwrap().deref();
                 let value_left = self.evaluate_expr(
-                    mtree_left, rcc_frame.clone());
+                    mtree_left, rcc_frame.clone())?;
                 let value_right = self.evaluate_expr(
-                    mtree_right, rcc_frame.clone());
-                value_left.binaryOp(code, value_right)
+                    mtree_right, rcc_frame.clone())?;
+                value_left.binaryOp(code, value_right, self.overflow)
+                    .map_err(|e| e.at_default(&mtree_expr.token.loc))?
 
             } else {
-                panic!();
+                return Err(Error::runtime(format!(
+                    "Operator {:?} expects 1 or 2 operands, got {}", code, mtree_expr.children.len()))
+                    .at(mtree_expr.token.loc.clone()));
             }
 
         }
@@ -262,36 +586,35 @@ impl Evaluator {
             // get storage location (LHS)
             let mtree_left= mtree_expr.children.get(0).unwrap().deref();
             let loc_left = match  & mtree_left.token.code {
-                TCode::A_REF(loc) => { loc.clone() }
-                _ => { panic!("Left operand of assignment must be REF!"); }
+                TCode::A_REF(loc) => { *loc }
+                _ => { return Err(Error::runtime("Left operand of assignment must be REF!")) }
             };
 
-            // get id.
-            let mtree_id = mtree_expr.children.get(0).unwrap().deref();
-
             // get value (RHS)
             let mtree_right= mtree_expr.children.get(1).unwrap().deref();
             let value_right = self.evaluate_expr(
-                mtree_right, rcc_frame.clone());
+                mtree_right, rcc_frame.clone())?;
 
             // assign value to storage location
-            rcc_frame.borrow_mut().value_store(&loc_left, value_right.clone());
+            rcc_frame.borrow_mut().value_store(&loc_left, value_right.clone())
+                .map_err(|e| e.at_default(&mtree_left.token.loc))?;
             value_right
 
         }
         else {
-            panic!("Code: {:?}", code)
+            return Err(Error::runtime(format!("Can't evaluate code {:?}", code))
+                .at(mtree_expr.token.loc.clone()));
         };
 
         self.log.debug(format!("value={:?}", value).as_str());
         self.log.indent_dec();
-        value
+        Ok(value)
     }
 
 
     pub fn evaluate_call(
         &mut self, mtree_call: &MTree, rcc_frame: Rc<RefCell<CFrame>>)
-        -> DValue
+        -> Result<DValue>
     {
         self.log.debug("evaluate_call()");
         self.log.indent_inc();
@@ -300,19 +623,33 @@ impl Evaluator {
         let mtree_ref = mtree_call.children.get(0).unwrap().deref();
         let mtree_func = match &mtree_ref.token.code {
             TCode::A_REF(loc) => {
-                let value = rcc_frame.borrow().value_load(loc);
+                let value = rcc_frame.borrow().value_load(loc)
+                    .map_err(|e| e.at_default(&mtree_ref.token.loc))?;
                 if let DValue::FUNC(rc_func) = value {
                     rc_func
                 } else {
-                    panic!("Expected DValue::FUNC!")
+                    return Err(Error::runtime(format!("Expected function, got {}!", value))
+                        .at(mtree_ref.token.loc.clone()));
                 }
             }
-            _ => { panic!("Expected REF!") }
+            _ => { return Err(Error::runtime("Expected REF!")) }
         };
 
-        // create new call frame (CFrame) and link with current call frame
-        let n_args = mtree_call.children.len() - 1; // number of call arguments
-        let cFrame = CFrame::new(n_args);
+        // function value may be passed around, so number of arguments is checked here
+        let n_params = mtree_func.children.get(1).map_or(0, |rc_params| rc_params.children.len());
+        let n_args = mtree_call.children.len() - 1;
+        if n_params != n_args {
+            return Err(Error::runtime(format!("Function {}!", arity_mismatch(n_params, n_args)))
+                .at(mtree_ref.token.loc.clone())
+                .with_label(mtree_func.token.loc.first.clone(), "function defined here"));
+        }
+
+        // create new call frame (CFrame) for parameters and variables of function
+        let size_cFrame = match &mtree_func.children.first().unwrap().token.code {
+            TCode::A_BLOCK(rcc_aFrame) => { rcc_aFrame.borrow().size_symbols(FrameTyp::FUNCTION) }
+            _ => { return Err(Error::runtime("Expected Code::META_BLOCK tree!")) }
+        };
+        let cFrame = CFrame::new(size_cFrame);
         let rcc_frame_func = Rc::new(RefCell::new(cFrame));
         rcc_frame_func.borrow_mut().cFrame_up = rcc_frame.borrow().cFrame_up.clone();
 
@@ -325,21 +662,21 @@ impl Evaluator {
             let mtree_arg = child.deref();
             let value_arg = self.evaluate_expr(
                 mtree_arg, rcc_frame.clone()
-            );
+            )?;
             rcc_frame_func.borrow_mut().value_store_cell(idx_arg, value_arg)
         }
 
         // evaluate function
-        let value = self.evaluate_func(mtree_func.deref(), rcc_frame_func);
+        let value = self.evaluate_func(mtree_func.deref(), rcc_frame_func)?;
 
         self.log.indent_dec();
-        value
+        Ok(value)
     }
 
 
     pub fn evaluate_func(
         &mut self, mtree_func: &MTree, rcc_frame_func: Rc<RefCell<CFrame>>)
-        -> DValue
+        -> Result<DValue>
     {
         self.log.debug("evaluate_func()");
         self.log.indent_inc();
@@ -349,14 +686,456 @@ impl Evaluator {
         if let TCode::A_BLOCK(rcc_statics) = &mtree2_block.token.code {
             rcc_frame_func.borrow_mut().aFrame = Some(rcc_statics.clone());
         } else {
-            panic!("Expected Code::META_BLOCK tree!")
+            return Err(Error::runtime("Expected Code::META_BLOCK tree!"));
         }
 
-        // evaluate block with already created FUNCTION frame
-        let (value, _) = self.evaluate_block_framed(mtree2_block, rcc_frame_func);
+        // evaluate block with already created FUNCTION frame,
+        // result is value of return (or TOK, if end of function is reached)
+        let value = match self.evaluate_block_framed(mtree2_block, rcc_frame_func)? {
+            (value, Control::RETURN) => { value }
+            _ => { DValue::TOK }
+        };
 
         self.log.indent_dec();
-        value
+        Ok(value)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::rc::Rc;
+    use crate::analyzer::Analyzer;
+    use crate::error::{Error, ErrorKind, Result};
+    use crate::hw_assignment_3::Lexer;
+    use crate::hw_assignment_4::Parser;
+    use super::Evaluator;
+
+    /// run program with given input, result and content written to output buffer
+    fn run(source: &str, input: &str, prefix: bool) -> (Result<()>, String) {
+        let Ok(lexer) = source.parse::<Lexer>();
+        let mut parser = Parser::new(lexer);
+        let tree = parser.analyze().unwrap();
+        assert!(parser.errors.is_empty(), "syntax errors: {:?}", parser.errors);
+        let tree_analyzed = Analyzer::new().analyze_global(Rc::new(tree)).unwrap();
+
+        let mut evaluator = Evaluator::new();
+        evaluator.set_input(Cursor::new(String::from(input)));
+        evaluator.set_output_buffer();
+        evaluator.write_prefix = prefix;
+        let result = evaluator.evaluate(&tree_analyzed);
+        (result, evaluator.take_output().unwrap())
+    }
+
+    const PROGRAM_WRITE: &str = "func main() [ write 1 + 2; write \"ab\"; write 'c'; ]";
+
+    #[test]
+    fn write_to_buffer_with_prefix() {
+        let (result, output) = run(PROGRAM_WRITE, "", true);
+        assert!(result.is_ok());
+        assert_eq!(output, "> 3\n> ab\n> c\n");
+    }
+
+    #[test]
+    fn write_to_buffer_without_prefix() {
+        let (result, output) = run(PROGRAM_WRITE, "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "3\nab\nc\n");
+    }
+
+    #[test]
+    fn read_values_from_injected_input() {
+        let program = "func main() [
+            let mut b; let mut i; let mut f; let mut c; let mut s;
+            read b; read i; read f; read c; read s;
+            write not b; write i + 1; write f * 2; write c; write s + \"!\";
+        ]";
+        let (result, output) = run(program, "false\n41\n1.25\nz\nhello world\n", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "true\n42\n2.5\nz\nhello world!\n");
+    }
+
+    #[test]
+    fn read_at_end_of_input_is_error() {
+        let program = "func main() [ let mut x; read x; read x; ]";
+        let (result, _) = run(program, "1\n", false);
+        let error = result.unwrap_err();
+        assert_eq!(error.msg, "Unexpected end of input!");
+        // position of second read
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 34));
     }
 
+    #[test]
+    fn error_in_let_initializer_has_position() {
+        let program = "func main()\n[\n    let x = 1 / 0;\n]";
+        let (result, _) = run(program, "", false);
+        let error = result.unwrap_err();
+        assert!(!error.loc.is_empty());
+        assert_eq!(error.loc.first.row, 3);
+    }
+
+    #[test]
+    fn call_passed_in_function() {
+        let program = "func inc(x) [ return x + 1; ] func twice(f, x) [ return f(f(x)); ] func main() [ write twice(inc, 1); ]";
+        let (result, output) = run(program, "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "3\n");
+    }
+
+    #[test]
+    fn call_passed_in_function_with_wrong_number_of_arguments_is_error() {
+        let program = "func add(x, y) [ return x + y; ] func apply(f) [ return f(1); ] func main() [ write apply(add); ]";
+        let (result, output) = run(program, "", false);
+        let error = result.unwrap_err();
+        assert_eq!(error.msg, "Function takes 2 argument(s) but 1 was given!");
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 57));
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn call_of_non_function_is_error() {
+        let program = "func apply(f) [ return f(1); ] func main() [ write apply(2); ]";
+        let (result, _) = run(program, "", false);
+        let error = result.unwrap_err();
+        assert!(error.msg.starts_with("Expected function"), "{}", error.msg);
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 24));
+    }
+
+    #[test]
+    fn take_output_clears_buffer() {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_output_buffer();
+        evaluator.output.write_line("x").unwrap();
+        assert_eq!(evaluator.take_output().unwrap(), "x\n");
+        assert_eq!(evaluator.take_output().unwrap(), "");
+    }
+
+    /// result of comparisons (without prefix), one per line
+    fn comparisons(exprs: &[&str]) -> String {
+        let writes: Vec<String> = exprs.iter().map(|expr| format!("write {};", expr)).collect();
+        let (result, output) = run(&format!("func main() [ {} ]", writes.join(" ")), "", false);
+        assert!(result.is_ok(), "{:?}", result);
+        output
+    }
+
+    #[test]
+    fn compare_chars() {
+        let output = comparisons(&["'a' <= 'b'", "'b' <= 'b'", "'a' >= 'b'", "'a' == 'a'", "'a' != 'a'"]);
+        assert_eq!(output, "true\ntrue\nfalse\ntrue\nfalse\n");
+    }
+
+    #[test]
+    fn compare_bools() {
+        let output = comparisons(&["true == true", "true == false", "true != false", "false != false"]);
+        assert_eq!(output, "true\nfalse\ntrue\nfalse\n");
+    }
+
+    #[test]
+    fn compare_strs() {
+        let output = comparisons(&["\"abc\" <= \"abd\"", "\"b\" >= \"abc\"", "\"b\" <= \"abc\"", "\"x\" == \"x\"", "\"x\" != \"x\""]);
+        assert_eq!(output, "true\ntrue\nfalse\ntrue\nfalse\n");
+    }
+
+    #[test]
+    fn compare_numbers_with_le_and_ge() {
+        let output = comparisons(&["2 <= 2", "1.5 >= 2", "3 >= 2.5"]);
+        assert_eq!(output, "true\nfalse\ntrue\n");
+    }
+
+    #[test]
+    fn compare_values_of_different_type_is_error() {
+        let (result, _) = run("func main() [ write \"1\" == 1; ]", "", false);
+        let error = result.unwrap_err();
+        assert_eq!(error.kind, ErrorKind::RUNTIME);
+        assert!(error.msg.starts_with("Both types need to be numeric for operator"), "{}", error.msg);
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 25));
+    }
+
+    #[test]
+    fn order_of_bools_is_error() {
+        let (result, _) = run("func main() [ write true <= false; ]", "", false);
+        let error = result.unwrap_err();
+        assert!(error.msg.ends_with("is undefined on BOOL!"), "{}", error.msg);
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 26));
+    }
+
+    const PROGRAM_SIDE: &str = "func side(b) [ write \"side\"; return b; ] func main() [ ";
+
+    #[test]
+    fn and_skips_right_operand_if_left_is_false() {
+        let (result, output) = run(&format!("{} write false and side(true); write true and side(false); ]", PROGRAM_SIDE), "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "false\nside\nfalse\n");
+    }
+
+    #[test]
+    fn or_skips_right_operand_if_left_is_true() {
+        let (result, output) = run(&format!("{} write true or side(false); write false or side(true); ]", PROGRAM_SIDE), "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "true\nside\ntrue\n");
+    }
+
+    #[test]
+    fn non_bool_operand_of_and_is_error() {
+        let (result, _) = run("func main() [ write true and 1; ]", "", false);
+        let error = result.unwrap_err();
+        assert!(error.msg.starts_with("Operator AND requires operands of type BOOL"), "{}", error.msg);
+        assert!(error.msg.ends_with("on the right!"), "{}", error.msg);
+        // position of right operand
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 30));
+    }
+
+    /// error of analysis of program
+    fn analysis_error(source: &str) -> Error {
+        let Ok(lexer) = source.parse::<Lexer>();
+        let mut parser = Parser::new(lexer);
+        let tree = parser.analyze().unwrap();
+        assert!(parser.errors.is_empty(), "syntax errors: {:?}", parser.errors);
+        Analyzer::new().analyze_global(Rc::new(tree)).expect_err("analysis error")
+    }
+
+    #[test]
+    fn break_and_continue_in_while() {
+        let program = "func main() [
+            let i = 0;
+            while true [
+                i = i + 1;
+                if i == 2 [ continue; ]
+                if i > 4 [ break; ]
+                write i;
+            ]
+            write 10 * i;
+        ]";
+        let (result, output) = run(program, "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "1\n3\n4\n50\n");
+    }
+
+    #[test]
+    fn break_and_continue_in_for() {
+        let program = "func main() [
+            for i in 0..10 [
+                if i % 2 == 1 [ continue; ]
+                if i == 6 [ break; ]
+                write i;
+            ]
+        ]";
+        let (result, output) = run(program, "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "0\n2\n4\n");
+    }
+
+    #[test]
+    fn break_leaves_inner_loop_only() {
+        let program = "func main() [
+            for i in 1..=2 [
+                while true [ break; ]
+                write i;
+            ]
+        ]";
+        let (result, output) = run(program, "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "1\n2\n");
+    }
+
+    #[test]
+    fn break_outside_of_loop_is_error() {
+        let error = analysis_error("func main() [ if true [ break; ] ]");
+        assert_eq!(error.kind, ErrorKind::SEMANTIC);
+        assert_eq!(error.msg, "BREAK outside of loop!");
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 25));
+    }
+
+    #[test]
+    fn continue_in_function_called_from_loop_is_error() {
+        let error = analysis_error("func f() [ continue; ] func main() [ while true [ f(); ] ]");
+        assert_eq!(error.msg, "CONTINUE outside of loop!");
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 12));
+    }
+
+    #[test]
+    fn return_from_nested_loops() {
+        let program = "func find(n) [
+            for i in 1..10 [
+                let j = 1;
+                while j < 10 [
+                    if i * j == n [ return i * 100 + j; ]
+                    j = j + 1;
+                ]
+            ]
+            return 0;
+        ]
+        func main() [ write find(12); write find(97); ]";
+        let (result, output) = run(program, "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "206\n0\n");
+    }
+
+    #[test]
+    fn return_leaves_rest_of_function() {
+        let program = "func f() [ if true [ while true [ return 1; ] ] write \"unreachable\"; ] func main() [ write f(); ]";
+        let (result, output) = run(program, "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "1\n");
+    }
+
+    #[test]
+    fn bare_return_yields_tok() {
+        let program = "func f() [ write 1; return; write 2; ] func g() [ ] func main() [ write f(); write g(); ]";
+        let (result, output) = run(program, "", false);
+        assert!(result.is_ok());
+        // TOK is written as ●, same as for end of function
+        assert_eq!(output, "1\n●\n●\n");
+    }
+
+    /// values of loop variable (without prefix), separated by spaces
+    fn loop_values(header: &str) -> Result<String> {
+        let (result, output) = run(&format!("func main() [ for x in {} [ write x; ] ]", header), "", false);
+        result.map(|_| output.lines().collect::<Vec<&str>>().join(" "))
+    }
+
+    #[test]
+    fn for_over_exclusive_range() {
+        assert_eq!(loop_values("1..4").unwrap(), "1 2 3");
+        assert_eq!(loop_values("3..3").unwrap(), "");
+    }
+
+    #[test]
+    fn for_over_inclusive_range() {
+        assert_eq!(loop_values("1..=4").unwrap(), "1 2 3 4");
+        assert_eq!(loop_values("3..=3").unwrap(), "3");
+    }
+
+    #[test]
+    fn for_over_range_with_step() {
+        assert_eq!(loop_values("0..10 step 3").unwrap(), "0 3 6 9");
+        assert_eq!(loop_values("0..=9 step 3").unwrap(), "0 3 6 9");
+        assert_eq!(loop_values("5..0 step -2").unwrap(), "5 3 1");
+        assert_eq!(loop_values("4..=0 step -2").unwrap(), "4 2 0");
+    }
+
+    #[test]
+    fn for_over_range_with_step_zero_is_error() {
+        let error = loop_values("0..10 step 0").unwrap_err();
+        assert_eq!(error.msg, "Step of range must not be 0!");
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 35));
+    }
+
+    #[test]
+    fn for_over_range_ends_at_i64_max() {
+        assert_eq!(loop_values("9223372036854775806..=9223372036854775807").unwrap(),
+            "9223372036854775806 9223372036854775807");
+    }
+
+    #[test]
+    fn for_over_str() {
+        assert_eq!(loop_values("\"añb\"").unwrap(), "a ñ b");
+        assert_eq!(loop_values("\"\"").unwrap(), "");
+    }
+
+    #[test]
+    fn for_over_bool_is_error() {
+        let error = loop_values("true").unwrap_err();
+        assert!(error.msg.starts_with("Can't iterate over value of type"), "{}", error.msg);
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 24));
+    }
+
+    const PROGRAM_SIGN: &str = "func sign(n) [
+        if n < 0 [ return \"negative\"; ]
+        else if n == 0 [ return \"zero\"; ]
+        else if n < 10 [ return \"small\"; ]
+        else [ return \"large\"; ]
+    ]";
+
+    #[test]
+    fn else_if_chain_takes_first_true_branch() {
+        let program = format!("{} func main() [ write sign(-5); write sign(0); write sign(5); write sign(50); ]", PROGRAM_SIGN);
+        let (result, output) = run(&program, "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "negative\nzero\nsmall\nlarge\n");
+    }
+
+    #[test]
+    fn else_if_chain_without_else() {
+        let program = "func main() [
+            for i in 0..3 [
+                if i == 0 [ write \"a\"; ] else if i == 1 [ write \"b\"; ]
+            ]
+        ]";
+        let (result, output) = run(program, "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "a\nb\n");
+    }
+
+    #[test]
+    fn else_if_condition_must_be_bool() {
+        let (result, _) = run("func main() [ if false [ ] else if 1 [ ] ]", "", false);
+        let error = result.unwrap_err();
+        assert_eq!(error.msg, "Condition must result in value of type Bool!");
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 36));
+    }
+
+    #[test]
+    fn let_shadows_in_nested_block() {
+        let program = "func main() [
+            let x = 1;
+            if true [
+                let x = x + 10;
+                write x;
+                if true [ let x = \"inner\"; write x; ]
+                write x;
+            ]
+            write x;
+        ]";
+        let (result, output) = run(program, "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "11\ninner\n11\n1\n");
+    }
+
+    #[test]
+    fn let_shadows_in_same_block() {
+        let (result, output) = run("func main() [ let x = 1; let x = x * 2; write x; ]", "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "2\n");
+    }
+
+    #[test]
+    fn assignment_in_nested_block_changes_outer_variable() {
+        let (result, output) = run("func main() [ let x = 1; while x < 3 [ x = x + 1; ] write x; ]", "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "3\n");
+    }
+
+    #[test]
+    fn let_in_loop_is_new_binding_per_iteration() {
+        let (result, output) = run("func main() [ for i in 0..2 [ let y; write y; y = i; ] ]", "", false);
+        assert!(result.is_ok());
+        assert_eq!(output, "●\n●\n");
+    }
+
+    #[test]
+    fn assignment_to_undeclared_variable_is_error() {
+        let error = analysis_error("func main() [ x = 1; ]");
+        assert_eq!(error.kind, ErrorKind::SEMANTIC);
+        assert_eq!(error.msg, "Assignment to undeclared variable 'x'!");
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 15));
+    }
+
+    #[test]
+    fn variable_of_nested_block_is_out_of_scope_after_block() {
+        let error = analysis_error("func main() [ if true [ let y = 1; ] y = 2; ]");
+        assert_eq!(error.msg, "Assignment to undeclared variable 'y'!");
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 38));
+    }
+
+    #[test]
+    fn literal_of_i64_min() {
+        let program = "func main() [ let m = -9223372036854775808; write m; write m == -9223372036854775807 - 1; write -m; ]";
+        let (result, output) = run(program, "", false);
+        assert_eq!(output, "-9223372036854775808\ntrue\n");
+        let error = result.unwrap_err();
+        assert!(error.msg.starts_with("Integer overflow"), "{}", error.msg);
+    }
 }
\ No newline at end of file
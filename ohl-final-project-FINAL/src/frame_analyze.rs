@@ -3,6 +3,8 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
+use crate::error::{Error, Result};
+use crate::token::TPos;
 use crate::typ::{Typ};
 use crate::value::DValue;
 
@@ -53,6 +55,7 @@ pub struct ASymbol {
     pub typ: Typ,               // symbol type (currently Typ::D for dynamic value)
     pub loc: CellLoc,           // symbol location in some frame
     pub value: DValue,          // current symbol value
+    pub pos: TPos,              // position of declaration (or empty)
 }
 
 
@@ -65,6 +68,7 @@ impl ASymbol {
             mutable: false,
             typ: Typ::D,
             value: DValue::TOK,
+            pos: TPos::new(0, 0, 0),
         }
     }
 }
@@ -162,7 +166,7 @@ impl AFrame {
     }
 
 
-    pub fn value_load(&self, loc: &CellLoc) -> DValue {
+    pub fn value_load(&self, loc: &CellLoc) -> Result<DValue> {
         if loc.idx_frame == 0 {
             let symbols = match loc.typ {
                 FrameTyp::PROGRAM => { &self.symbols_program }
@@ -170,9 +174,9 @@ impl AFrame {
             };
             match symbols.get(loc.idx_cell) {
                 Some(aSymbol) => {
-                    aSymbol.value.clone()
+                    Ok(aSymbol.value.clone())
                 }
-                None => { panic!("Can't find symbol at {:?}", loc) }
+                None => { Err(Error::runtime(format!("Can't find symbol at {:?}", loc))) }
             }
 
         } else {
@@ -181,13 +185,13 @@ impl AFrame {
                     let loc = loc.idx_store_dec();
                     rcc_frame.borrow_mut().value_load(&loc)
                 }
-                None => { panic!("AFrame up is missing!")}
+                None => { Err(Error::runtime("AFrame up is missing!")) }
             }
         }
     }
 
 
-    pub fn value_store_init(&mut self, loc: &CellLoc, value: DValue, init: bool) {
+    pub fn value_store_init(&mut self, loc: &CellLoc, value: DValue, init: bool) -> Result<()> {
         if loc.idx_frame == 0 {
             let symbols = match loc.typ {
                 FrameTyp::PROGRAM => { &mut self.symbols_program }
@@ -197,27 +201,28 @@ impl AFrame {
                 Some(aSymbol) => {
                     if aSymbol.mutable || init {
                         aSymbol.value = value;
+                        Ok(())
                     } else {
-                        panic!("Can't store to immutable cell!")
+                        Err(Error::runtime(format!("Can't store to immutable cell '{}'!", aSymbol.name)))
                     }
                 }
-                None => { panic!("Can't find symbol at {:?}", loc) }
+                None => { Err(Error::runtime(format!("Can't find symbol at {:?}", loc))) }
             }
 
         } else {
             match &self.frame_up {
                 Some(rcc_frame) => {
                     let loc = loc.idx_store_dec();
-                    rcc_frame.borrow_mut().value_store_init(&loc, value, init);
+                    rcc_frame.borrow_mut().value_store_init(&loc, value, init)
                 }
-                None => { panic!("AFrame up is missing!")}
+                None => { Err(Error::runtime("AFrame up is missing!")) }
             }
         }
     }
 
 
-    pub fn value_store(&mut self, loc: &CellLoc, value: DValue) {
-        self.value_store_init(loc, value, false);
+    pub fn value_store(&mut self, loc: &CellLoc, value: DValue) -> Result<()> {
+        self.value_store_init(loc, value, false)
     }
 
 
@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 use std::rc::Rc;
+use crate::error::{Error, Result};
 use crate::frame_analyze::{AFrame, CellLoc, FrameTyp};
 use crate::value::DValue;
 
@@ -25,26 +26,29 @@ impl CFrame {
 
 
     /// load a value relative to this frame
-    pub fn value_load(&self, loc: &CellLoc) -> DValue {
+    pub fn value_load(&self, loc: &CellLoc) -> Result<DValue> {
         match loc.typ {
             FrameTyp::PROGRAM => { // values with 'process' lifetime
                 match &self.aFrame {
                     Some(rcc_aFrame) => {
                         rcc_aFrame.borrow().value_load(loc)
                     }
-                    None => { panic!("AFrame is missing!") }
+                    None => { Err(Error::runtime("AFrame is missing!")) }
                 }
             }
             FrameTyp::FUNCTION => { // values on call stack
                 if loc.idx_frame == 0 {
-                    self.dValues.get(loc.idx_cell).unwrap().clone()
+                    match self.dValues.get(loc.idx_cell) {
+                        Some(value) => { Ok(value.clone()) }
+                        None => { Err(Error::runtime(format!("Can't find cell at {:?}", loc))) }
+                    }
                 } else {
                     match &self.cFrame_up {
                         Some(rcc_up) => {
                             let loc_up = loc.idx_store_dec();
                             rcc_up.borrow().value_load(&loc_up)
                         }
-                        None => { panic!("CFrame up is missing!") }
+                        None => { Err(Error::runtime("CFrame up is missing!")) }
                     }
                 }
             }
@@ -54,35 +58,55 @@ impl CFrame {
 
     /// store a value in this call frame
     pub fn value_store_cell(&mut self, idx_cell: usize, value: DValue) {
-        self.dValues.insert(idx_cell, value);
+        if idx_cell >= self.dValues.len() {
+            self.dValues.resize(idx_cell + 1, DValue::TOK);
+        }
+        self.dValues[idx_cell] = value;
+    }
+
+
+    /// initialize a value relative to this frame (immutable cells, too)
+    pub fn value_init(&mut self, loc: &CellLoc, value: DValue) -> Result<()> {
+        match loc.typ {
+            FrameTyp::PROGRAM => {
+                match &self.aFrame {
+                    Some(rcc_aFrame) => {
+                        rcc_aFrame.borrow_mut().value_store_init(loc, value, true)
+                    }
+                    None => { Err(Error::runtime("AFrame is missing!")) }
+                }
+            }
+            FrameTyp::FUNCTION => { self.value_store(loc, value) }
+        }
     }
 
 
     /// store a value relative to this frame
-    pub fn value_store(&mut self, loc: &CellLoc, value: DValue) {
+    pub fn value_store(&mut self, loc: &CellLoc, value: DValue) -> Result<()> {
         match loc.typ {
             FrameTyp::PROGRAM => { // values with 'process' lifetime
                 match &self.aFrame {
                     Some(rcc_aFrame) => {
-                        rcc_aFrame.borrow_mut().value_store(loc, value);
+                        rcc_aFrame.borrow_mut().value_store(loc, value)
                     }
-                    None => { panic!("AFrame is missing!") }
+                    None => { Err(Error::runtime("AFrame is missing!")) }
                 }
             }
             FrameTyp::FUNCTION => { // values on call stack
                 if loc.idx_frame == 0 {
-                    self.dValues.insert(loc.idx_cell, value);
+                    self.value_store_cell(loc.idx_cell, value);
+                    Ok(())
                 } else {
                     match &self.cFrame_up {
                         Some(rcc_up) => {
                             let loc_up = loc.idx_store_dec();
-                            rcc_up.borrow_mut().value_store(&loc_up, value);
+                            rcc_up.borrow_mut().value_store(&loc_up, value)
                         }
-                        None => { panic!("CFrame up is missing!") }
+                        None => { Err(Error::runtime("CFrame up is missing!")) }
                     }
                 }
             }
         }
     }
 
-}
\ No newline at end of file
+}
@@ -1,8 +1,13 @@
 #![allow(non_camel_case_types)]
 #![allow(dead_code)]
 
+use std::convert::Infallible;
 use std::fs;
-use crate::token::{TCode};
+use std::io::Read;
+use std::str::FromStr;
+use unicode_xid::UnicodeXID;
+use crate::error::{Error, Result};
+use crate::token::{TCode, TLoc, TPos, Token};
 use crate::value::DValue;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -12,6 +17,7 @@ pub enum LexerState {
     Stage,
     NumberInt,
     NumberFloat,
+    NumberExp,
     NumberChar,
     NumberString,
     NumberArray,
@@ -28,8 +34,14 @@ pub struct Lexer {
     pub input_pos: usize,
     pub state: LexerState,
     pub token: Option<TCode>,
+    pub loc: TLoc,              // location of current token
     pub buffer_string: String,
     pub tokens : Vec<TCode>,
+    row: usize,                 // current row (starting at 1)
+    line_start: usize,          // input position of current row
+    token_start: usize,         // input position of current token
+    token_prev: Option<TCode>,  // token before current token
+    pub doc_comments: Vec<(TPos, String)>,  // text of '///' comments (trivia for doc generators)
 }
 
 impl Lexer {
@@ -39,30 +51,285 @@ impl Lexer {
             input_pos: 0,
             state: LexerState::Start,
             token: None,
+            loc: TLoc::empty(),
             buffer_string: String::new(),
             tokens : vec![],
+            row: 1,
+            line_start: 0,
+            token_start: 0,
+            token_prev: None,
+            doc_comments: vec![],
         }
     }
 
-    pub fn set_input(&mut self, input: String) {
-        let input_string = fs::read_to_string(input).expect("Should have been able to read the input file");
+    /// lexer on source code read from reader (such as stdin)
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Lexer> {
+        let mut input_string = String::new();
+        if let Err(e) = reader.read_to_string(&mut input_string) {
+            return Err(Error::io(format!("Can't read input: {}", e)));
+        }
+        let mut lexer = Lexer::new();
+        lexer.set_input_string(input_string);
+        Ok(lexer)
+    }
+
+    /// read source code from file with given path
+    pub fn set_input(&mut self, input: String) -> Result<()> {
+        let input_string = match fs::read_to_string(&input) {
+            Ok(input_string) => { input_string }
+            Err(e) => { return Err(Error::io(format!("Can't read file {}: {}", input, e))) }
+        };
+        self.set_input_string(input_string);
+        Ok(())
+    }
+
+    /// restart lexer on given source code
+    pub fn set_input_string(&mut self, input_string: String) {
         self.input_string = input_string;
         self.input_pos = 0;
         self.state = LexerState::Start;
         self.token = None;
+        self.token_prev = None;
+        self.loc = TLoc::empty();
         self.buffer_string.clear();
+        self.row = 1;
+        self.line_start = 0;
+        self.token_start = 0;
+        self.doc_comments.clear();
+    }
+
+    /// position from start of current token up to current input position
+    /// (columns and length count characters, not bytes)
+    fn token_pos(&self) -> TPos {
+        let col = self.input_string[self.line_start..self.token_start].chars().count() + 1;
+        let len = self.input_string[self.token_start..self.input_pos].chars().count();
+        TPos::new(self.row, col, len)
+    }
+
+    /// make token current and record its location
+    fn emit(&mut self, token: TCode) -> Result<TCode> {
+        self.loc = TLoc::at(self.token_pos());
+        self.token = Some(token.clone());
+        Ok(token)
+    }
+
+    /// lexical error at current token (which becomes an ERROR token)
+    fn error(&mut self, msg: impl Into<String>) -> Error {
+        let pos = self.token_pos();
+        self.error_at(pos, msg)
+    }
+
+    /// lexical error at given position (which becomes an ERROR token)
+    fn error_at(&mut self, pos: TPos, msg: impl Into<String>) -> Error {
+        self.loc = TLoc::at(pos);
+        self.token = Some(TCode::ERROR);
+        Error::lex(msg).at(self.loc.clone())
     }
 
     pub fn get_next_character_is_value(&mut self, val: char) -> bool {
-        return self.input_pos + 1 < self.input_string.len() && self.input_string.as_bytes()[self.input_pos + 1] as char == val;
+        return self.peek_char() == Some(val);
     }
 
+    /// character at current input position
     fn next_char(&self) -> Option<char> {
-        self.input_string.as_bytes().get(self.input_pos).map(|&b| b as char)
+        self.input_string[self.input_pos..].chars().next()
     }
 
+    /// character following the one at current input position
     fn peek_char(&self) -> Option<char> {
-        self.input_string.as_bytes().get(self.input_pos + 1).map(|&b| b as char)
+        self.input_string[self.input_pos..].chars().nth(1)
+    }
+
+    /// 'e' or 'E' at current position starts an exponent (such as e-3 or E10)
+    fn exponent_follows(&self) -> bool {
+        let mut chars = self.input_string[self.input_pos..].chars().skip(1);
+        match chars.next() {
+            Some('+') | Some('-') => { chars.next().is_some_and(|c| c.is_ascii_digit()) }
+            Some(c) => { c.is_ascii_digit() }
+            None => { false }
+        }
+    }
+
+    fn is_id_start(c: char) -> bool {
+        c == '_' || c.is_xid_start()
+    }
+
+    fn is_id_continue(c: char) -> bool {
+        c.is_xid_continue()
+    }
+
+    /// keyword or identifier in buffer
+    fn emit_word(&mut self) -> Result<TCode> {
+        let token = match self.buffer_string.as_str() {
+            "true" => TCode::VAL(DValue::BOOL(true)),
+            "false" => TCode::VAL(DValue::BOOL(false)),
+            "func" => TCode::FUNC,
+            "let" => TCode::LET,
+            "mut" => TCode::MUT,
+            "const" => TCode::CONST,
+            "if" => TCode::IF,
+            "else" => TCode::ELSE,
+            "while" => TCode::WHILE,
+            "for" => TCode::FOR,
+            "in" => TCode::IN,
+            //"print" => TCode::PRINT,
+            "return" => TCode::RETURN,
+            "break" => TCode::BREAK,
+            "continue" => TCode::CONTINUE,
+            "not" => TCode::NOT,
+            "and" => TCode::AND,
+            "or" => TCode::OR,
+            _ => TCode::ID(self.buffer_string.clone()),
+        };
+        self.state = LexerState::Start;
+        self.buffer_string.clear();
+        self.emit(token)
+    }
+
+    /// base of integer literal in buffer (prefix 0x or 0b)
+    fn int_radix(&self) -> u32 {
+        match self.buffer_string.get(..2) {
+            Some("0x") | Some("0X") => { 16 }
+            Some("0b") | Some("0B") => { 2 }
+            _ => { 10 }
+        }
+    }
+
+    /// parse buffer as integer literal (decimal, hex or binary, with optional '_' separators)
+    fn emit_int(&mut self) -> Result<TCode> {
+        let radix = self.int_radix();
+        let text = self.buffer_string.clone();
+        let digits: String = text.chars().skip(if radix == 10 { 0 } else { 2 }).filter(|&c| c != '_').collect();
+        self.buffer_string.clear();
+        self.state = LexerState::Start;
+
+        if digits.is_empty() {
+            return Err(self.error(format!("Missing digits in integer literal {}", text)));
+        }
+        match i64::from_str_radix(&digits, radix) {
+            Ok(val) => { self.emit(TCode::VAL(DValue::I64(val))) }
+            // magnitude of I64::MIN behind '-' is kept as I64::MIN, see Parser::parse_min_literal()
+            Err(_) if self.token_prev == Some(TCode::SUB) && u64::from_str_radix(&digits, radix) == Ok(1 << 63) => {
+                self.emit(TCode::VAL(DValue::I64(i64::MIN)))
+            }
+            Err(_) => { Err(self.error(format!("Integer literal {} is out of range for I64", text))) }
+        }
+    }
+
+    /// parse buffer as floating point literal
+    fn emit_float(&mut self) -> Result<TCode> {
+        let parsed = self.buffer_string.replace('_', "").parse::<f64>();
+        self.buffer_string.clear();
+        self.state = LexerState::Start;
+        match parsed {
+            Ok(val) => { self.emit(TCode::VAL(DValue::F64(val))) }
+            Err(e) => { Err(self.error(format!("Invalid value for float: {}", e))) }
+        }
+    }
+    /// lexical error for exponent without digits (such as 1e or 2.5e+)
+    fn missing_exponent(&mut self) -> Error {
+        let mut chars = self.input_string[self.input_pos..].chars();
+        self.buffer_string.extend(chars.next());
+        self.input_pos += 1;
+        if let Some(sign @ ('+' | '-')) = chars.next() {
+            self.buffer_string.push(sign);
+            self.input_pos += 1;
+        }
+        let literal = std::mem::take(&mut self.buffer_string);
+        self.state = LexerState::Start;
+        self.error(format!("Missing digits in exponent of float literal {}", literal))
+    }
+
+    /// escape sequence at current position (such as \n, \' or \u{2022})
+    fn scan_escape(&mut self) -> Result<char> {
+        self.input_pos += 1;
+        let c = match self.next_char() {
+            Some(c) if c != '\n' => { c }
+            _ => { return Err(self.error("Unterminated escape sequence")) }
+        };
+        self.input_pos += c.len_utf8();
+        match c {
+            'n' => { Ok('\n') }
+            't' => { Ok('\t') }
+            'r' => { Ok('\r') }
+            '0' => { Ok('\0') }
+            '\\' | '\'' | '"' => { Ok(c) }
+            'u' => {
+                if self.next_char() != Some('{') {
+                    return Err(self.error("Expected '{' after \\u"));
+                }
+                self.input_pos += 1;
+                let mut hex = String::new();
+                while let Some(h) = self.next_char().filter(|h| h.is_ascii_hexdigit()) {
+                    hex.push(h);
+                    self.input_pos += 1;
+                }
+                if self.next_char() != Some('}') {
+                    return Err(self.error("Expected '}' to close unicode escape"));
+                }
+                self.input_pos += 1;
+                match u32::from_str_radix(&hex, 16).ok().filter(|_| hex.len() <= 6).and_then(char::from_u32) {
+                    Some(c) => { Ok(c) }
+                    None => { Err(self.error(format!("Invalid unicode escape \\u{{{}}}", hex))) }
+                }
+            }
+            _ => { Err(self.error(format!("Unknown escape sequence \\{}", c))) }
+        }
+    }
+
+    /// skip rest of a broken literal (up to closing quote or end of line)
+    fn skip_literal(&mut self, quote: char) {
+        while let Some(c) = self.next_char() {
+            if c == '\n' {
+                break;
+            }
+            self.input_pos += c.len_utf8();
+            if c == quote {
+                break;
+            }
+            if c == '\\' && let Some(escaped) = self.next_char().filter(|&c| c != '\n') {
+                self.input_pos += escaped.len_utf8();
+            }
+        }
+        self.buffer_string.clear();
+        self.state = LexerState::Start;
+    }
+    /// skip '//' comment up to end of line, '///' comments are kept as doc comments
+    fn skip_line_comment(&mut self) {
+        let pos = self.token_pos();
+        let end = self.input_string[self.input_pos..].find('\n')
+            .map_or(self.input_string.len(), |i| self.input_pos + i);
+        let text = &self.input_string[self.input_pos..end];
+        if let Some(doc) = text.strip_prefix("///").filter(|doc| !doc.starts_with('/')) {
+            let doc = doc.strip_prefix(' ').unwrap_or(doc);
+            self.doc_comments.push((pos, String::from(doc.trim_end())));
+        }
+        self.input_pos = end;
+    }
+
+    /// skip '/* ... */' comment, which may be nested and span several lines
+    fn skip_block_comment(&mut self) -> Result<()> {
+        let pos_open = TPos::new(self.row, self.token_pos().col, 2);
+        let mut depth = 0;
+        while let Some(c) = self.next_char() {
+            if c == '/' && self.peek_char() == Some('*') {
+                depth += 1;
+                self.input_pos += 2;
+            } else if c == '*' && self.peek_char() == Some('/') {
+                depth -= 1;
+                self.input_pos += 2;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else {
+                self.input_pos += c.len_utf8();
+                if c == '\n' {
+                    self.row += 1;
+                    self.line_start = self.input_pos;
+                }
+            }
+        }
+        Err(self.error_at(pos_open, "Unterminated block comment"))
     }
     /*
     pub fn skip_whitespace(&mut self) {
@@ -76,32 +343,32 @@ impl Lexer {
         }
     }
     */
-    pub fn advance(&mut self) -> Option<TCode> {
-        self.token = None;
+    pub fn advance(&mut self) -> Result<TCode> {
+        self.token_prev = self.token.take();
 
         while self.input_pos < self.input_string.len() {
-            let current_char: char = self.input_string.as_bytes()[self.input_pos] as char;
-            // let current_char: char = self.next_char().unwrap();
+            let current_char: char = self.next_char().unwrap();
             match self.state {
                 LexerState::Start => {
                     if vec!['\n'].contains(&current_char) {
                         self.input_pos += 1;
-                        println!();
+                        self.row += 1;
+                        self.line_start = self.input_pos;
                         continue;
                     }
                     if current_char.is_whitespace() {
-                        self.input_pos += 1;
+                        self.input_pos += current_char.len_utf8();
                         continue;
                     }
 
                     if self.input_pos >= self.input_string.len() {
                         let token = TCode::EOI;
-                        self.token = Some(token.clone());
-                        return Some(token);
+                        return self.emit(token);
                     }
+                    self.token_start = self.input_pos;
                     self.buffer_string.clear();
-                    if current_char.is_alphabetic() || current_char == '_' || current_char.is_digit(10) {
-                        self.input_pos += 1;
+                    if Self::is_id_start(current_char) || current_char.is_ascii_digit() {
+                        self.input_pos += current_char.len_utf8();
                         self.buffer_string.push(current_char);
                         self.state = if current_char.is_digit(10) { LexerState::NumberInt } else { LexerState::Operation };
                         continue;
@@ -132,7 +399,8 @@ impl Lexer {
                                     self.input_pos += 1;
                                     token = TCode::AND;
                                 } else {
-                                    token = TCode::ERROR;
+                                    self.input_pos += 1;
+                                    return Err(self.error("Invalid character '&', expected '&&'"));
                                 }
                             },
                             '|' => {
@@ -140,138 +408,240 @@ impl Lexer {
                                     self.input_pos += 1;
                                     token = TCode::OR;
                                 } else {
-                                    token = TCode::ERROR;
+                                    self.input_pos += 1;
+                                    return Err(self.error("Invalid character '|', expected '||'"));
                                 }
                             }
                             _ => token = TCode::ID(current_char.to_string()),
                         }
                         self.input_pos += 1;
-                        self.token = Some(token.clone());
-                        return Some(token)
+                        return self.emit(token);
                     }
 
                     if current_char == '+' {
-                        let peek = self.peek_char();
                         let token = TCode::ADD;
                         self.input_pos += 1;
-                        self.token = Some(token.clone());
-                        return Some(token);
+                        return self.emit(token);
                     }
                     if current_char == '*' {
                         let peek = self.peek_char();
-                        let token = TCode::MULT;
+                        let token = if peek == Some('*') { TCode::POW } else { TCode::MULT };
+                        self.input_pos += if peek == Some('*') { 2 } else { 1 };
+                        return self.emit(token);
+                    }
+                    if current_char == '%' {
+                        let token = TCode::MOD;
                         self.input_pos += 1;
-                        self.token = Some(token.clone());
-                        return Some(token);
+                        return self.emit(token);
                     }
                     if current_char == '/' {
                         let peek = self.peek_char();
+                        if peek == Some('/') {
+                            self.skip_line_comment();
+                            continue;
+                        }
+                        if peek == Some('*') {
+                            self.skip_block_comment()?;
+                            continue;
+                        }
                         let token = TCode::DIV;
                         self.input_pos += 1;
-                        self.token = Some(token.clone());
-                        return Some(token);
+                        return self.emit(token);
                     }
 
                     if vec!['=', '<', '>', '!'].contains(&current_char) {
-                        let token: TCode;
                         let has_found_equals: bool = self.peek_char() == Some('=');
-                        match current_char {
-                            '=' => token = if has_found_equals { TCode::EQ } else { TCode::ASSIGN },
-                            '<' => token = TCode::LT,
-                            '>' => token = TCode::GT,
-                            '!' => {
-                                if self.input_pos + 1 < self.input_string.len() {
-                                    token = if has_found_equals {TCode::NOT_EQ } else { TCode::NOT }
-                                } else {
-                                    panic!("Invalid character: !");
-                                }
-                            },
-                            _ => {token = TCode::ID(current_char.to_string())}
-                        }
+                        let token = match current_char {
+                            '=' => if has_found_equals { TCode::EQ } else { TCode::ASSIGN },
+                            '<' => if has_found_equals { TCode::LE } else { TCode::LT },
+                            '>' => if has_found_equals { TCode::GE } else { TCode::GT },
+                            '!' => if has_found_equals { TCode::NOT_EQ } else { TCode::NOT },
+                            _ => TCode::ID(current_char.to_string()),
+                        };
                         self.input_pos += if has_found_equals { 2 } else { 1 };
-                        self.token = Some(token.clone());
-                        return Some(token);
+                        return self.emit(token);
+                    }
+                    if current_char == '.' && self.peek_char() == Some('.') {
+                        self.input_pos += 2;
+                        let token = if self.next_char() == Some('=') {
+                            self.input_pos += 1;
+                            TCode::DOT_DOT_EQ
+                        } else {
+                            TCode::DOT_DOT
+                        };
+                        return self.emit(token);
                     }
                     if current_char == '-' {
-                        let next = self.peek_char();
                         let token = TCode::SUB;
-                        // self.input_pos += if next.is_some() { 2 } else { 1 };
                         self.input_pos += 1;
-                        self.token = Some(token.clone());
-                        return Some(token);
+                        return self.emit(token);
                     }
+
+                    self.input_pos += current_char.len_utf8();
+                    return Err(self.error(format!("Invalid character '{}'", current_char)));
                 }
                 LexerState::Operation => {
-                    if current_char.is_alphanumeric() || current_char == '_' {
+                    if Self::is_id_continue(current_char) {
                         self.buffer_string.push(current_char);
-                        self.input_pos += 1;
+                        self.input_pos += current_char.len_utf8();
                         continue;
                     } else {
-                        let token = match self.buffer_string.as_str() {
-                            "true" => TCode::VAL(DValue::BOOL(true)),
-                            "false" => TCode::VAL(DValue::BOOL(false)),
-                            "func" => TCode::FUNC,
-                            "let" => TCode::LET,
-                            "if" => TCode::IF,
-                            "else" => TCode::ELSE,
-                            "while" => TCode::WHILE,
-                            //"print" => TCode::PRINT,
-                            "return" => TCode::RETURN,
-                            "not" => TCode::NOT,
-                            "and" => TCode::AND,
-                            "or" => TCode::OR,
-                            _ => TCode::ID(self.buffer_string.clone()),
-                        };
-                        self.state = LexerState::Start;
-                        self.buffer_string.clear();
-                        self.token = Some(token.clone());
-                        return Some(token);
+                        return self.emit_word();
                     }
                 }
                 LexerState::NumberInt => {
-                    if current_char.is_digit(10) || current_char == '.' {
+                    let radix = self.int_radix();
+                    let is_prefix = self.buffer_string == "0" && matches!(current_char, 'x' | 'X' | 'b' | 'B');
+                    // '.' belongs to the number unless a range or an identifier follows (3.14 and 1., but not 1..3)
+                    let is_fraction = radix == 10 && current_char == '.'
+                        && !self.peek_char().is_some_and(|c| c == '.' || Self::is_id_start(c));
+                    let is_exponent = radix == 10 && (current_char == 'e' || current_char == 'E')
+                        && self.exponent_follows();
+                    if current_char.is_digit(radix) || current_char == '_' || is_prefix || is_fraction || is_exponent {
                         self.buffer_string.push(current_char);
                         self.input_pos += 1;
-                        if current_char == '.' {
+                        if is_fraction {
                             self.state = LexerState::NumberFloat;
                         }
+                        if is_exponent {
+                            self.state = LexerState::NumberExp;
+                        }
                         continue;
-                    } else {
-                        let val = self.buffer_string.parse::<i32>().expect("Invalid value for integer");
+                    } else if radix == 10 && (current_char == 'e' || current_char == 'E') {
+                        return Err(self.missing_exponent());
+                    } else if radix != 10 && current_char.is_ascii_alphanumeric() {
+                        self.input_pos += 1;
                         self.buffer_string.clear();
                         self.state = LexerState::Start;
-                        let token = TCode::VAL(DValue::I64(val as i64));
-                        self.token = Some(token.clone());
-                        return Some(token);
+                        return Err(self.error(format!("Invalid digit '{}' for base {} literal", current_char, radix)));
+                    } else {
+                        return self.emit_int();
                     }
                 }
-                /*LexerState::StringLit => {
-                    if current_char == '"' {
-                        let buffer_clone = self.buffer_string.clone();
-                        self.buffer_string.clear();
+                LexerState::NumberFloat => {
+                    if current_char.is_ascii_digit() {
+                        self.buffer_string.push(current_char);
                         self.input_pos += 1;
-                        self.state = LexerState::Start;
-                        let token = TCode::LIT_STRING(buffer_clone);
-                        self.token = Some(token.clone());
-                        return Some(token);
+                        continue;
+                    } else if (current_char == 'e' || current_char == 'E') && self.exponent_follows() {
+                        self.buffer_string.push(current_char);
+                        self.input_pos += 1;
+                        self.state = LexerState::NumberExp;
+                        continue;
+                    } else if current_char == 'e' || current_char == 'E' {
+                        return Err(self.missing_exponent());
                     } else {
+                        return self.emit_float();
+                    }
+                }
+                LexerState::NumberExp => {
+                    let is_sign = (current_char == '+' || current_char == '-')
+                        && self.buffer_string.ends_with(['e', 'E']);
+                    if current_char.is_ascii_digit() || is_sign {
                         self.buffer_string.push(current_char);
                         self.input_pos += 1;
                         continue;
+                    } else {
+                        return self.emit_float();
+                    }
+                }
+                LexerState::CharLit => {
+                    match current_char {
+                        '\'' => {
+                            self.input_pos += 1;
+                            self.state = LexerState::Start;
+                            let mut chars = self.buffer_string.chars();
+                            return match (chars.next(), chars.next()) {
+                                (Some(c), None) => { self.emit(TCode::VAL(DValue::CHAR(c))) }
+                                (None, _) => { Err(self.error("Empty character literal")) }
+                                _ => { Err(self.error("Character literal must contain exactly one character")) }
+                            };
+                        }
+                        '\n' => {
+                            self.skip_literal('\'');
+                            return Err(self.error("Unterminated character literal"));
+                        }
+                        '\\' => {
+                            match self.scan_escape() {
+                                Ok(c) => { self.buffer_string.push(c); }
+                                Err(e) => {
+                                    self.skip_literal('\'');
+                                    return Err(e);
+                                }
+                            }
+                        }
+                        _ => {
+                            self.buffer_string.push(current_char);
+                            self.input_pos += current_char.len_utf8();
+                        }
                     }
-                }*/
-                _ => {}
+                    continue;
+                }
+                LexerState::StringLit => {
+                    match current_char {
+                        '"' => {
+                            let buffer_clone = self.buffer_string.clone();
+                            self.buffer_string.clear();
+                            self.input_pos += 1;
+                            self.state = LexerState::Start;
+                            let token = TCode::VAL(DValue::STR(buffer_clone));
+                            return self.emit(token);
+                        }
+                        '\n' => {
+                            self.skip_literal('"');
+                            return Err(self.error("Unterminated string literal"));
+                        }
+                        '\\' => {
+                            match self.scan_escape() {
+                                Ok(c) => { self.buffer_string.push(c); }
+                                Err(e) => {
+                                    self.skip_literal('"');
+                                    return Err(e);
+                                }
+                            }
+                        }
+                        _ => {
+                            self.buffer_string.push(current_char);
+                            self.input_pos += current_char.len_utf8();
+                        }
+                    }
+                    continue;
+                }
+                state => {
+                    self.buffer_string.clear();
+                    self.state = LexerState::Start;
+                    return Err(self.error(format!("Unsupported literal ({:?})", state)));
+                }
             }
         }
-        let token = TCode::EOI;
-        self.token = Some(token.clone());
-        Some(token)
+        // end of input terminates pending token
+        match self.state {
+            LexerState::Operation => { return self.emit_word() }
+            LexerState::NumberInt => { return self.emit_int() }
+            LexerState::NumberFloat | LexerState::NumberExp => { return self.emit_float() }
+            LexerState::CharLit => {
+                self.skip_literal('\'');
+                return Err(self.error("Unterminated character literal"));
+            }
+            LexerState::StringLit => {
+                self.skip_literal('"');
+                return Err(self.error("Unterminated string literal"));
+            }
+            _ => {}
+        }
+        self.token_start = self.input_pos;
+        self.emit(TCode::EOI)
     }
 
     pub fn curr(&self) -> TCode {
         self.token.clone().unwrap_or(TCode::EOI)
     }
 
+    /// current token with its location
+    pub fn curr_token(&self) -> Token {
+        Token { code: self.curr(), loc: self.loc.clone() }
+    }
+
     pub fn print_token(&mut self) {
         let token = self.token.clone().unwrap();
 
@@ -285,4 +655,287 @@ impl Lexer {
             }
         }
     }
+}
+
+
+/// lexer on source code in memory, such as "x = 1;".parse::<Lexer>()
+impl FromStr for Lexer {
+    type Err = Infallible;
+
+    fn from_str(input: &str) -> std::result::Result<Lexer, Infallible> {
+        let mut lexer = Lexer::new();
+        lexer.set_input_string(String::from(input));
+        Ok(lexer)
+    }
+}
+
+
+/// tokens (with their locations) up to end of input, lexical errors yield ERROR tokens
+impl Iterator for Lexer {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        if self.token == Some(TCode::EOI) {
+            return None;
+        }
+        match self.advance() {
+            Ok(TCode::EOI) => { None }
+            Ok(_) | Err(_) => { Some(self.curr_token()) }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use crate::token::TCode;
+    use super::Lexer;
+
+    type Pos = (usize, usize, usize);   // row, col, len
+
+    /// tokens of iterator (as text) with their positions
+    fn lex(source: &str) -> Vec<(String, Pos)> {
+        let Ok(lexer) = source.parse::<Lexer>();
+        lexer
+            .map(|token| {
+                let pos = token.loc.first;
+                (format!("{:?}", token.code), (pos.row, pos.col, pos.len))
+            })
+            .collect()
+    }
+
+    /// tokens (as text) of iterator
+    fn codes(source: &str) -> Vec<String> {
+        lex(source).into_iter().map(|(code, _)| code).collect()
+    }
+
+    /// messages and positions of lexical errors
+    fn lex_errors(source: &str) -> Vec<(String, Pos)> {
+        let Ok(mut lexer) = source.parse::<Lexer>();
+        let mut errors = vec![];
+        loop {
+            match lexer.advance() {
+                Ok(TCode::EOI) => { return errors; }
+                Ok(_) => {}
+                Err(e) => { errors.push((e.msg, (e.loc.first.row, e.loc.first.col, e.loc.first.len))); }
+            }
+        }
+    }
+
+    #[test]
+    fn iterator_yields_tokens_with_positions() {
+        let tokens = lex("let x = 10;\n  write x;");
+        let expected = vec![
+            ("LET", (1, 1, 3)),
+            ("ID(\"x\")", (1, 5, 1)),
+            (":=", (1, 7, 1)),
+            ("VAL(I64(10))", (1, 9, 2)),
+            ("SEMICOLON", (1, 11, 1)),
+            ("ID(\"write\")", (2, 3, 5)),
+            ("ID(\"x\")", (2, 9, 1)),
+            ("SEMICOLON", (2, 10, 1)),
+        ];
+        let expected: Vec<(String, Pos)> = expected.into_iter().map(|(c, p)| (String::from(c), p)).collect();
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn iterator_continues_after_error_token() {
+        assert_eq!(lex("a $ b"), vec![
+            (String::from("ID(\"a\")"), (1, 1, 1)),
+            (String::from("ERROR"), (1, 3, 1)),
+            (String::from("ID(\"b\")"), (1, 5, 1)),
+        ]);
+    }
+
+    #[test]
+    fn iterator_of_empty_input_is_empty() {
+        assert!(lex("").is_empty());
+        assert!(lex("  \n ").is_empty());
+    }
+
+    #[test]
+    fn float_literals() {
+        assert_eq!(lex("1.5"), vec![(String::from("VAL(F64(1.5))"), (1, 1, 3))]);
+        assert_eq!(lex("1e3"), vec![(String::from("VAL(F64(1000.0))"), (1, 1, 3))]);
+        assert_eq!(lex("1.5e-3"), vec![(String::from("VAL(F64(0.0015))"), (1, 1, 6))]);
+        assert_eq!(codes("2E+2 0.25"), ["VAL(F64(200.0))", "VAL(F64(0.25))"]);
+    }
+
+    #[test]
+    fn float_literal_with_trailing_dot() {
+        assert_eq!(lex("1."), vec![(String::from("VAL(F64(1.0))"), (1, 1, 2))]);
+        assert_eq!(codes("1. + x"), ["VAL(F64(1.0))", "ADD", "ID(\"x\")"]);
+    }
+
+    #[test]
+    fn range_is_no_float_literal() {
+        assert_eq!(codes("1..3"), ["VAL(I64(1))", "DOT_DOT", "VAL(I64(3))"]);
+        assert_eq!(codes("1..=3"), ["VAL(I64(1))", "DOT_DOT_EQ", "VAL(I64(3))"]);
+    }
+
+    #[test]
+    fn exponent_without_digits_is_error() {
+        assert_eq!(lex_errors("1e"), vec![
+            (String::from("Missing digits in exponent of float literal 1e"), (1, 1, 2)),
+        ]);
+        assert_eq!(lex_errors("x = 2.5e+;"), vec![
+            (String::from("Missing digits in exponent of float literal 2.5e+"), (1, 5, 5)),
+        ]);
+        assert_eq!(codes("1e;"), ["ERROR", "SEMICOLON"]);
+    }
+
+    #[test]
+    fn int_literals_with_prefix_and_underscores() {
+        assert_eq!(codes("0x1F 0b101 1_000_000"), ["VAL(I64(31))", "VAL(I64(5))", "VAL(I64(1000000))"]);
+        assert_eq!(lex("1__0"), vec![(String::from("VAL(I64(10))"), (1, 1, 4))]);
+        assert_eq!(lex("1_"), vec![(String::from("VAL(I64(1))"), (1, 1, 2))]);
+    }
+
+    #[test]
+    fn int_literal_without_digits_or_with_invalid_digit() {
+        assert_eq!(lex_errors("0x"), vec![
+            (String::from("Missing digits in integer literal 0x"), (1, 1, 2)),
+        ]);
+        assert_eq!(lex_errors("0b2"), vec![
+            (String::from("Invalid digit '2' for base 2 literal"), (1, 1, 3)),
+        ]);
+    }
+
+    #[test]
+    fn int_literal_range_of_i64() {
+        assert_eq!(codes("9223372036854775807"), ["VAL(I64(9223372036854775807))"]);
+        assert_eq!(lex_errors("x = 9223372036854775808;"), vec![
+            (String::from("Integer literal 9223372036854775808 is out of range for I64"), (1, 5, 19)),
+        ]);
+        assert_eq!(lex_errors("0xFFFF_FFFF_FFFF_FFFF"), vec![
+            (String::from("Integer literal 0xFFFF_FFFF_FFFF_FFFF is out of range for I64"), (1, 1, 21)),
+        ]);
+        assert_eq!(codes("0x7FFF_FFFF_FFFF_FFFF"), ["VAL(I64(9223372036854775807))"]);
+    }
+
+    #[test]
+    fn int_literal_of_magnitude_of_i64_min_behind_minus() {
+        // negated by parser
+        assert_eq!(codes("-9223372036854775808"), ["SUB", "VAL(I64(-9223372036854775808))"]);
+        assert_eq!(codes("-0x8000_0000_0000_0000"), ["SUB", "VAL(I64(-9223372036854775808))"]);
+        assert_eq!(lex_errors("-9223372036854775809"), vec![
+            (String::from("Integer literal 9223372036854775809 is out of range for I64"), (1, 2, 19)),
+        ]);
+    }
+
+    #[test]
+    fn char_literals_with_escapes() {
+        assert_eq!(codes(r"'a' '\n' '\'' '\\' '\u{2022}' 'é'"), [
+            "VAL(CHAR('a'))", "VAL(CHAR('\\n'))", "VAL(CHAR('\\''))",
+            "VAL(CHAR('\\\\'))", "VAL(CHAR('•'))", "VAL(CHAR('é'))",
+        ]);
+        assert_eq!(lex(r"'\u{2022}'"), vec![(String::from("VAL(CHAR('•'))"), (1, 1, 10))]);
+    }
+
+    #[test]
+    fn invalid_char_literals() {
+        assert_eq!(lex_errors("''"), vec![(String::from("Empty character literal"), (1, 1, 2))]);
+        assert_eq!(lex_errors("'ab'"), vec![
+            (String::from("Character literal must contain exactly one character"), (1, 1, 4)),
+        ]);
+        assert_eq!(lex_errors(r"'\q'"), vec![(String::from("Unknown escape sequence \\q"), (1, 1, 3))]);
+        assert_eq!(lex_errors(r"'\u{110000}'"), vec![
+            (String::from("Invalid unicode escape \\u{110000}"), (1, 1, 11)),
+        ]);
+        // rest of invalid literal is skipped
+        assert_eq!(codes(r"'\q' x"), ["ERROR", "ID(\"x\")"]);
+    }
+
+    #[test]
+    fn unterminated_char_literal() {
+        assert_eq!(lex_errors("x = 'a"), vec![(String::from("Unterminated character literal"), (1, 5, 2))]);
+        assert_eq!(codes("'a\nx"), ["ERROR", "ID(\"x\")"]);
+    }
+
+    #[test]
+    fn string_literals_with_escapes() {
+        assert_eq!(lex(r#""a\tb\"c\u{e9}""#), vec![(String::from("VAL(STR(\"a\\tb\\\"cé\"))"), (1, 1, 15))]);
+        assert_eq!(codes(r#""" "it's" "\\n""#), [
+            "VAL(STR(\"\"))", "VAL(STR(\"it's\"))", "VAL(STR(\"\\\\n\"))",
+        ]);
+        assert_eq!(lex_errors(r#"x = "a\qb""#), vec![(String::from("Unknown escape sequence \\q"), (1, 5, 4))]);
+    }
+
+    #[test]
+    fn unterminated_string_literal() {
+        assert_eq!(lex_errors("x = \"abc"), vec![(String::from("Unterminated string literal"), (1, 5, 4))]);
+        // literal ends at line break, lexing goes on in next line
+        assert_eq!(lex_errors("\"ab\ny"), vec![(String::from("Unterminated string literal"), (1, 1, 3))]);
+        assert_eq!(codes("\"ab\ny"), ["ERROR", "ID(\"y\")"]);
+    }
+
+    #[test]
+    fn columns_count_chars_after_non_ascii() {
+        assert_eq!(lex("é = \"ü•\"; x"), vec![
+            (String::from("ID(\"é\")"), (1, 1, 1)),
+            (String::from(":="), (1, 3, 1)),
+            (String::from("VAL(STR(\"ü•\"))"), (1, 5, 4)),
+            (String::from("SEMICOLON"), (1, 9, 1)),
+            (String::from("ID(\"x\")"), (1, 11, 1)),
+        ]);
+        assert_eq!(lex("// ⊤ ⊥ ●\n  y"), vec![(String::from("ID(\"y\")"), (2, 3, 1))]);
+    }
+
+    #[test]
+    fn byte_offsets_after_non_ascii() {
+        let Ok(mut lexer) = "é = 'ü'; x".parse::<Lexer>();
+        let mut offsets = vec![];
+        while lexer.advance().unwrap() != TCode::EOI {
+            offsets.push(lexer.input_pos);
+        }
+        // end of é (2 bytes), =, 'ü' (4 bytes), ;, x
+        assert_eq!(offsets, [2, 4, 9, 10, 12]);
+    }
+
+    #[test]
+    fn identifiers_follow_unicode_xid() {
+        assert_eq!(codes("naïve _λ x1"), ["ID(\"naïve\")", "ID(\"_λ\")", "ID(\"x1\")"]);
+        assert_eq!(lex_errors("a € b"), vec![(String::from("Invalid character '€'"), (1, 3, 1))]);
+    }
+
+    #[test]
+    fn line_and_nested_block_comments_are_skipped() {
+        assert_eq!(codes("x // y\nz"), ["ID(\"x\")", "ID(\"z\")"]);
+        assert_eq!(lex("/* a /* b */ c */ x"), vec![(String::from("ID(\"x\")"), (1, 19, 1))]);
+        // rows are counted inside of block comments
+        assert_eq!(lex("x\n  /* \n /* */ */ y"), vec![
+            (String::from("ID(\"x\")"), (1, 1, 1)),
+            (String::from("ID(\"y\")"), (3, 11, 1)),
+        ]);
+        assert_eq!(codes("1 /2"), ["VAL(I64(1))", "DIV", "VAL(I64(2))"]);
+    }
+
+    #[test]
+    fn unterminated_block_comment() {
+        // error points at outermost opening delimiter
+        assert_eq!(lex_errors("x\n /* a /* b */ c"), vec![
+            (String::from("Unterminated block comment"), (2, 2, 2)),
+        ]);
+    }
+
+    #[test]
+    fn doc_comments_are_kept() {
+        let Ok(mut lexer) = "/// doc text\n//// no doc\n// no doc\n  ///second\nx".parse::<Lexer>();
+        let codes: Vec<String> = lexer.by_ref().map(|token| format!("{:?}", token.code)).collect();
+        assert_eq!(codes, ["ID(\"x\")"]);
+        let docs: Vec<(Pos, &str)> = lexer.doc_comments.iter()
+            .map(|(pos, text)| ((pos.row, pos.col, pos.len), text.as_str()))
+            .collect();
+        assert_eq!(docs, [((1, 1, 0), "doc text"), ((4, 3, 0), "second")]);
+    }
+
+    #[test]
+    fn lexer_from_reader() {
+        let lexer = Lexer::from_reader(Cursor::new("x + 1")).unwrap();
+        let codes_reader: Vec<String> = lexer.map(|token| format!("{:?}", token.code)).collect();
+        assert_eq!(codes_reader, codes("x + 1"));
+        assert_eq!(codes_reader, ["ID(\"x\")", "ADD", "VAL(I64(1))"]);
+    }
 }
\ No newline at end of file
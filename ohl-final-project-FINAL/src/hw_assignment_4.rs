@@ -3,34 +3,47 @@
 #![allow(non_snake_case)]
 
 use std::rc::Rc;
+use crate::error::{Error, Result};
 use crate::hw_assignment_3::*;
 use crate::token::*;
 use crate::mtree::*;
+use crate::value::DValue;
 
 const INDENT: usize = 2;
 
 pub struct Parser {
     pub(crate) lexer: Lexer,
     pub(crate) indent: usize,
+    pub recover: bool,          // keep parsing after a syntax error
+    pub errors: Vec<Error>,     // errors recorded while recovering
 }
 
 impl Parser {
+    /// lexical error of first token is recorded in `errors` (see analyze())
     pub fn new(mut lexer: Lexer) -> Parser {
-        lexer.advance();
-        Parser { lexer, indent: 0 }
+        let mut errors = vec![];
+        if let Err(e) = lexer.advance() {
+            errors.push(e);
+        }
+        Parser { lexer, indent: 0, recover: true, errors }
     }
 
-    pub fn analyze(&mut self) -> MTree {
+    /// parse program, errors are either returned (first one) or collected in `errors`
+    /// when recovering; the tree then contains ERROR nodes in place of broken parts
+    pub fn analyze(&mut self) -> Result<MTree> {
         self.indent = 0;
-        let mut tree = self.parse_program();
-        self.expect(TCode::EOI);
+        if !self.recover && !self.errors.is_empty() {
+            return Err(self.errors.remove(0));
+        }
+        let mut tree = self.parse_program()?;
+        self.expect(TCode::EOI)?;
 
         let main_func = MTree {
             token: Token::from(TCode::CALL),
             children: vec![Rc::new(MTree::new(Token::id("main")))],
         };
         tree._push(main_func);
-        tree
+        Ok(tree)
     }
 }
 // Utility methods
@@ -39,29 +52,84 @@ impl Parser {
         self.lexer.curr()
     }
 
-    pub fn advance(&mut self) {
-        self.lexer.advance();
+    pub fn curr_token(&self) -> Token {
+        self.lexer.curr_token()
+    }
+
+    /// syntax error at current token
+    fn error(&self, msg: impl Into<String>) -> Error {
+        Error::parse(msg).at(self.lexer.loc.clone())
+    }
+
+    pub fn advance(&mut self) -> Result<()> {
+        self.lexer.advance()?;
+        Ok(())
+    }
+
+    /// record error and continue (if recovering), otherwise give up
+    fn report(&mut self, error: Error) -> Result<()> {
+        if self.recover {
+            self.errors.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    /// skip tokens (panic mode) up to the end of the current statement:
+    /// behind the next SEMICOLON or nested block, or before BRACE_R, FUNC or EOI
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.curr() {
+                TCode::EOI => { return }
+                TCode::FUNC if depth == 0 => { return }
+                TCode::BRACE_R if depth == 0 => { return }
+                TCode::SEMICOLON if depth == 0 => {
+                    self.skip();
+                    return;
+                }
+                TCode::BRACE_L => { depth += 1; }
+                TCode::BRACE_R => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.skip();
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.skip();
+        }
+    }
+
+    /// advance while recovering, lexical errors are recorded as well
+    fn skip(&mut self) {
+        if let Err(e) = self.lexer.advance() {
+            self.errors.push(e);
+        }
     }
 
     pub fn peek(&self, symbol: TCode) -> bool {
         self.curr() == symbol
     }
 
-    pub fn expect(&mut self, symbol: TCode) {
+    pub fn expect(&mut self, symbol: TCode) -> Result<()> {
         if self.curr() == symbol {
-            self.advance();
+            self.advance()?;
             println!("{:<indent$}expect({symbol:?})", "", indent = self.indent);
+            Ok(())
         } else {
-            panic!("Expected {:?}, got {:?}", symbol, self.curr());
+            Err(self.error(format!("Expected {:?}, got {:?}", symbol, self.curr())))
         }
     }
 
-    pub fn accept(&mut self, symbol: TCode) -> bool {
+    pub fn accept(&mut self, symbol: TCode) -> Result<bool> {
         if self.curr() == symbol {
-            self.advance();
-            true
+            self.advance()?;
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
@@ -69,10 +137,10 @@ impl Parser {
         matches!(&self.curr(), TCode::ID(s) if s == name)
     }
 
-    fn expect_id(&mut self, name: &str) {
+    fn expect_id(&mut self, name: &str) -> Result<()> {
         match self.curr() {
             TCode::ID(s) if s == name => self.advance(),
-            _ => panic!("Expected identifier '{}'", name),
+            _ => Err(self.error(format!("Expected identifier '{}', got {:?}", name, self.curr()))),
         }
     }
 }
@@ -94,365 +162,548 @@ impl Parser {
 
 // Recursive descent parser
 impl Parser {
-    // program = { func }
-    pub fn parse_program(&mut self) -> MTree {
+    // program = { func | let_stmt }
+    pub fn parse_program(&mut self) -> Result<MTree> {
         self.indent_print("parse_program()");
         self.indent_inc();
         let mut global = MTree::new(Token::from(TCode::BLOCK));
+        let indent = self.indent;
 
         while !self.peek(TCode::EOI) {
-            let func = self.parse_func();
-            global._push(func);
+            // global variables and constants
+            let is_global = self.peek(TCode::LET) || self.peek(TCode::CONST);
+            let result = if is_global {
+                self.parse_let_stmt()
+            } else {
+                self.parse_func()
+            };
+            match result {
+                Ok(item) => { global._push(item); }
+                Err(e) => {
+                    global._push(MTree::new(Token { code: TCode::ERROR, loc: e.loc.clone() }));
+                    self.report(e)?;
+                    self.indent = indent;
+                    if is_global {
+                        // skip behind end of declaration or to next item
+                        while !matches!(self.curr(), TCode::SEMICOLON | TCode::LET | TCode::CONST | TCode::FUNC | TCode::EOI) {
+                            self.skip();
+                        }
+                        if self.peek(TCode::SEMICOLON) {
+                            self.skip();
+                        }
+                    } else {
+                        // skip to next function
+                        while !self.peek(TCode::FUNC) && !self.peek(TCode::EOI) {
+                            self.skip();
+                        }
+                    }
+                }
+            }
         }
 
         self.indent_dec();
-        global
+        Ok(global)
     }
 
     // func = FUNC ID PAREN_L [ ID { COMMA ID } ] PAREN_R block
-    pub fn parse_func(&mut self) -> MTree {
+    pub fn parse_func(&mut self) -> Result<MTree> {
         self.indent_print("parse_func()");
         self.indent_inc();
 
-        self.expect(TCode::FUNC);
-        let id = self.curr();
-        self.expect(TCode::ID(String::new()));
+        let token_func = self.curr_token();
+        self.expect(TCode::FUNC)?;
+        let id = self.curr_token();
+        self.expect(TCode::ID(String::new()))?;
 
-        let mut func_tree = MTree::new(Token::from(TCode::FUNC));
-        func_tree._push(MTree::new(Token::from(id)));
+        let mut func_tree = MTree::new(token_func);
+        func_tree._push(MTree::new(id));
 
-        let params = self.parse_params();
+        let params = self.parse_params()?;
         func_tree._push(params);
 
-        let body = self.parse_block();
+        let body = self.parse_block()?;
         func_tree._push(body);
 
         self.indent_dec();
-        func_tree
+        Ok(func_tree)
     }
 
     // params = PAREN_L [ ID { COMMA ID } ] PAREN_R
-    fn parse_params(&mut self) -> MTree {
+    fn parse_params(&mut self) -> Result<MTree> {
         self.indent_print("parse_params()");
         self.indent_inc();
 
-        let mut params_tree = MTree::new(Token::from(TCode::PARAMS));
-        self.expect(TCode::PAREN_L);
+        let mut params_tree = MTree::new(Token {
+            code: TCode::PARAMS,
+            loc: self.lexer.loc.clone(),
+        });
+        self.expect(TCode::PAREN_L)?;
 
-        if self.accept(TCode::PAREN_R) {
+        if self.peek(TCode::PAREN_R) {
+            params_tree.token.loc.last = self.lexer.loc.first.clone();
+            self.advance()?;
             self.indent_dec();
-            return params_tree;
+            return Ok(params_tree);
         }
 
         loop {
-            let param_id = self.curr();
-            self.expect(TCode::ID(String::new()));
-            params_tree._push(MTree::new(Token::from(param_id)));
-            if !self.accept(TCode::COMMA) {
+            let param_id = self.curr_token();
+            self.expect(TCode::ID(String::new()))?;
+            params_tree._push(MTree::new(param_id));
+            if !self.accept(TCode::COMMA)? {
                 break;
             }
         }
 
-        self.expect(TCode::PAREN_R);
+        params_tree.token.loc.last = self.lexer.loc.first.clone();
+        self.expect(TCode::PAREN_R)?;
         self.indent_dec();
-        params_tree
+        Ok(params_tree)
     }
 
     // block = BRACE_L { stmt } BRACE_R
-    fn parse_block(&mut self) -> MTree {
+    fn parse_block(&mut self) -> Result<MTree> {
         self.indent_print("parse_block()");
         self.indent_inc();
 
-        let mut block = MTree::new(Token::from(TCode::BLOCK));
-        self.expect(TCode::BRACE_L);
-
-        while !self.peek(TCode::BRACE_R) && !self.peek(TCode::EOI) {
-            let stmt = self.parse_stmt();
-            block._push(stmt);
+        let mut block = MTree::new(Token {
+            code: TCode::BLOCK,
+            loc: self.lexer.loc.clone(),
+        });
+        self.expect(TCode::BRACE_L)?;
+        let indent = self.indent;
+
+        while !self.peek(TCode::BRACE_R) && !self.peek(TCode::EOI) && !self.peek(TCode::FUNC) {
+            match self.parse_stmt() {
+                Ok(stmt) => { block._push(stmt); }
+                Err(e) => {
+                    block._push(MTree::new(Token { code: TCode::ERROR, loc: e.loc.clone() }));
+                    self.report(e)?;
+                    self.indent = indent;
+                    self.synchronize();
+                }
+            }
         }
 
-        self.expect(TCode::BRACE_R);
+        block.token.loc.last = self.lexer.loc.first.clone();
+        if let Err(e) = self.expect(TCode::BRACE_R) {
+            self.report(e.with_label(block.token.loc.first.clone(), "block opened here"))?;
+        }
         self.indent_dec();
-        block
+        Ok(block)
     }
 
-    // stmt = let_stmt | assign_stmt | return_stmt | if_stmt | write_stmt | call_stmt
-    fn parse_stmt(&mut self) -> MTree {
+    // stmt = let_stmt | assign_stmt | return_stmt | if_stmt | while_stmt | for_stmt | jump_stmt
+    //      | read_stmt | write_stmt | call_stmt
+    fn parse_stmt(&mut self) -> Result<MTree> {
         self.indent_print("parse_stmt()");
         self.indent_inc();
 
         let tree = match self.curr() {
-            TCode::LET    => self.parse_let_stmt(),
-            TCode::RETURN => self.parse_return_stmt(),
-            TCode::IF     => self.parse_if_stmt(),
-            TCode::WHILE  => self.parse_while_stmt(),
-            _ if self.peek_id("write") || self.peek_id("print") => self.parse_write_stmt(),
+            TCode::LET | TCode::CONST => self.parse_let_stmt()?,
+            TCode::RETURN => self.parse_return_stmt()?,
+            TCode::IF     => self.parse_if_stmt()?,
+            TCode::WHILE  => self.parse_while_stmt()?,
+            TCode::FOR    => self.parse_for_stmt()?,
+            TCode::BREAK | TCode::CONTINUE => self.parse_jump_stmt()?,
+            _ if self.peek_id("read") => self.parse_read_stmt()?,
+            _ if self.peek_id("write") || self.peek_id("print") => self.parse_write_stmt()?,
             TCode::ID(_) => {
                 let saved_lexer = self.lexer.clone();
-                self.advance();
-                if self.accept(TCode::ASSIGN) {
+                self.advance()?;
+                if self.accept(TCode::ASSIGN)? {
                     self.lexer = saved_lexer;
-                    self.parse_assign_stmt()
+                    self.parse_assign_stmt()?
                 } else {
                     self.lexer = saved_lexer;
-                    self.parse_call_stmt()
+                    self.parse_call_stmt()?
                 }
             }
-            _ => panic!("Invalid statement starting with {:?}", self.curr()),
+            _ => return Err(self.error(format!("Invalid statement starting with {:?}", self.curr()))),
         };
 
         self.indent_dec();
-        tree
+        Ok(tree)
     }
 
-    // let id [ := expr ] ;
-    fn parse_let_stmt(&mut self) -> MTree {
+    // let_stmt = LET [ MUT ] ID [ ASSIGN expr ] SEMICOLON | CONST ID ASSIGN expr SEMICOLON
+    // tree: LET, MUT (let mut) or CONST with children ID [ expr ]
+    fn parse_let_stmt(&mut self) -> Result<MTree> {
         self.indent_print("parse_let_stmt()");
         self.indent_inc();
 
-        self.expect(TCode::LET);
-        let id = self.curr();
-        self.expect(TCode::ID(String::new()));
+        let mut token_let = self.curr_token();
+        let is_const = self.accept(TCode::CONST)?;
+        if !is_const {
+            self.expect(TCode::LET)?;
+            if self.accept(TCode::MUT)? {
+                token_let.code = TCode::MUT;
+            }
+        }
+        let id = self.curr_token();
+        self.expect(TCode::ID(String::new()))?;
 
-        let mut tree = MTree::new(Token::from(TCode::LET));
-        tree._push(MTree::new(Token::from(id)));
+        let mut tree = MTree::new(token_let);
+        tree._push(MTree::new(id));
 
-        if self.accept(TCode::ASSIGN) {
-            let expr = self.parse_expr();
+        if is_const {
+            self.expect(TCode::ASSIGN)?;
+            tree._push(self.parse_expr()?);
+        } else if self.accept(TCode::ASSIGN)? {
+            let expr = self.parse_expr()?;
             tree._push(expr);
         }
 
-        self.expect(TCode::SEMICOLON);
+        self.expect(TCode::SEMICOLON)?;
         self.indent_dec();
-        tree
+        Ok(tree)
     }
 
     // id := expr ;
-    fn parse_assign_stmt(&mut self) -> MTree {
+    fn parse_assign_stmt(&mut self) -> Result<MTree> {
         self.indent_print("parse_assign_stmt()");
         self.indent_inc();
 
-        let id = self.curr();
-        self.expect(TCode::ID(String::new()));
-        self.expect(TCode::ASSIGN);
+        let id = self.curr_token();
+        self.expect(TCode::ID(String::new()))?;
+        let token_assign = self.curr_token();
+        self.expect(TCode::ASSIGN)?;
 
-        let mut tree = MTree::new(Token::from(TCode::ASSIGN));
-        tree._push(MTree::new(Token::from(id)));
-        tree._push(self.parse_expr());
+        let mut tree = MTree::new(token_assign);
+        tree._push(MTree::new(id));
+        tree._push(self.parse_expr()?);
 
-        self.expect(TCode::SEMICOLON);
+        self.expect(TCode::SEMICOLON)?;
         self.indent_dec();
-        tree
+        Ok(tree)
 
     }
 
-    // return expr ;
-    fn parse_return_stmt(&mut self) -> MTree {
+    // return [ expr ] ;
+    fn parse_return_stmt(&mut self) -> Result<MTree> {
         self.indent_print("parse_return_stmt()");
         self.indent_inc();
 
-        self.expect(TCode::RETURN);
-        let mut tree = MTree::new(Token::from(TCode::RETURN));
-        tree._push(self.parse_expr());
+        let token_return = self.curr_token();
+        self.expect(TCode::RETURN)?;
+        let mut tree = MTree::new(token_return);
+        if !self.peek(TCode::SEMICOLON) {
+            tree._push(self.parse_expr()?);
+        }
         tree.print();
-        self.expect(TCode::SEMICOLON);
+        self.expect(TCode::SEMICOLON)?;
 
         self.indent_dec();
-        tree
+        Ok(tree)
     }
 
-    // if expr block [ else ( if expr block | block ) ]
-    fn parse_if_stmt(&mut self) -> MTree {
+    // if_stmt = IF expr block { ELSE IF expr block } [ ELSE block ]
+    // tree: IF with children cond, block, { cond, block }, [ else block ]
+    fn parse_if_stmt(&mut self) -> Result<MTree> {
         self.indent_print("parse_if_stmt()");
         self.indent_inc();
 
-        self.expect(TCode::IF);
-        let cond = self.parse_expr();
-
-        let then_block = self.parse_block();
+        let token_if = self.curr_token();
+        self.expect(TCode::IF)?;
 
-        let mut tree = MTree::new(Token::from(TCode::IF));
-        tree._push(cond);
-        tree._push(then_block);
+        let mut tree = MTree::new(token_if);
+        tree._push(self.parse_expr()?);
+        tree._push(self.parse_block()?);
 
-        if self.accept(TCode::ELSE) {
-            // tree._push(self.parse_block());
-            let else_part = if self.peek(TCode::IF) {
-                self.parse_if_stmt()
+        while self.accept(TCode::ELSE)? {
+            if self.accept(TCode::IF)? {
+                tree._push(self.parse_expr()?);
+                tree._push(self.parse_block()?);
             } else {
-                self.parse_block()
-            };
-            tree._push(else_part);
+                tree._push(self.parse_block()?);
+                break;
+            }
         }
         self.indent_dec();
-        tree
+        Ok(tree)
     }
 
-    fn parse_while_stmt(&mut self) -> MTree {
+    fn parse_while_stmt(&mut self) -> Result<MTree> {
         self.indent_print("parse_while_stmt()");
         self.indent_inc();
 
-        self.expect(TCode::WHILE);
-        let cond = self.parse_expr();
+        let token_while = self.curr_token();
+        self.expect(TCode::WHILE)?;
+        let cond = self.parse_expr()?;
 
-        let body = self.parse_block();
+        let body = self.parse_block()?;
 
-        let mut tree = MTree::new(Token::from(TCode::WHILE));
+        let mut tree = MTree::new(token_while);
         tree._push(cond);
         tree._push(body);
 
         self.indent_dec();
-        tree
+        Ok(tree)
+    }
+
+    // for_stmt = FOR ID IN expr [ ( DOT_DOT | DOT_DOT_EQ ) expr [ step expr ] ] block
+    fn parse_for_stmt(&mut self) -> Result<MTree> {
+        self.indent_print("parse_for_stmt()");
+        self.indent_inc();
+
+        let token_for = self.curr_token();
+        self.expect(TCode::FOR)?;
+        let id = self.curr_token();
+        self.expect(TCode::ID(String::new()))?;
+        self.expect(TCode::IN)?;
+
+        // range (start, end and optional step) or any iterable value
+        let expr = self.parse_expr()?;
+        let iterable = if matches!(self.curr(), TCode::DOT_DOT | TCode::DOT_DOT_EQ) {
+            let mut range = MTree::new(self.curr_token());
+            self.advance()?;
+            range._push(expr);
+            range._push(self.parse_expr()?);
+            if self.peek_id("step") {
+                self.advance()?;
+                range._push(self.parse_expr()?);
+            }
+            range
+        } else {
+            expr
+        };
+
+        let body = self.parse_block()?;
+
+        let mut tree = MTree::new(token_for);
+        tree._push(MTree::new(id));
+        tree._push(iterable);
+        tree._push(body);
+
+        self.indent_dec();
+        Ok(tree)
+    }
+
+    // jump_stmt = ( BREAK | CONTINUE ) SEMICOLON
+    fn parse_jump_stmt(&mut self) -> Result<MTree> {
+        self.indent_print("parse_jump_stmt()");
+        self.indent_inc();
+
+        let token_jump = self.curr_token();
+        self.advance()?;
+        self.expect(TCode::SEMICOLON)?;
+
+        self.indent_dec();
+        Ok(MTree::new(token_jump))
+    }
+
+    // read id ;
+    fn parse_read_stmt(&mut self) -> Result<MTree> {
+        self.indent_print("parse_read_stmt()");
+        self.indent_inc();
+
+        let loc_read = self.lexer.loc.clone();
+        self.expect_id("read")?;
+        let id = self.curr_token();
+        self.expect(TCode::ID(String::new()))?;
+
+        let mut tree = MTree::new(Token { code: TCode::READ, loc: loc_read });
+        tree._push(MTree::new(id));
+
+        self.expect(TCode::SEMICOLON)?;
+
+        self.indent_dec();
+        Ok(tree)
     }
 
     // print expr ;
-    fn parse_write_stmt(&mut self) -> MTree {
+    fn parse_write_stmt(&mut self) -> Result<MTree> {
         self.indent_print("parse_write_stmt()");
         self.indent_inc();
 
+        let loc_write = self.lexer.loc.clone();
         if self.peek_id("write") {
-            self.expect_id("write");
+            self.expect_id("write")?;
         } else {
-            self.expect_id("print");
+            self.expect_id("print")?;
         }
 
-        let arg = self.parse_expr();
+        let arg = self.parse_expr()?;
 
-        let mut tree = MTree::new(Token::from(TCode::WRITE));
+        let mut tree = MTree::new(Token { code: TCode::WRITE, loc: loc_write });
         tree._push(arg);
 
-        self.expect(TCode::SEMICOLON);
+        self.expect(TCode::SEMICOLON)?;
 
         self.indent_dec();
-        tree
+        Ok(tree)
     }
 
     // call_stmt = ID PAREN_L [ expr { COMMA expr } ] PAREN_R ;
-    fn parse_call_stmt(&mut self) -> MTree {
+    fn parse_call_stmt(&mut self) -> Result<MTree> {
         self.indent_print("parse_call_stmt()");
         self.indent_inc();
 
-        let call_tree = self.parse_call_expr();
-        self.expect(TCode::SEMICOLON);
+        let call_tree = self.parse_call_expr()?;
+        self.expect(TCode::SEMICOLON)?;
 
         self.indent_dec();
-        call_tree
+        Ok(call_tree)
     }
 
     // call_expr = ID PAREN_L [ expr { COMMA expr } ] PAREN_R
-    fn parse_call_expr(&mut self) -> MTree {
-        let id = self.curr();
-        self.expect(TCode::ID(String::new()));
-        self.expect(TCode::PAREN_L);
+    fn parse_call_expr(&mut self) -> Result<MTree> {
+        let id = self.curr_token();
+        self.expect(TCode::ID(String::new()))?;
+        self.expect(TCode::PAREN_L)?;
 
-        let mut call_tree = MTree::new(Token::from(TCode::CALL));
-        call_tree._push(MTree::new(Token::from(id)));
+        let mut call_tree = MTree::new(Token { code: TCode::CALL, loc: id.loc.clone() });
+        call_tree._push(MTree::new(id));
 
         if !self.peek(TCode::PAREN_R) {
             loop {
-                call_tree._push(self.parse_expr());
-                if !self.accept(TCode::COMMA) {
+                call_tree._push(self.parse_expr()?);
+                if !self.accept(TCode::COMMA)? {
                     break;
                 }
             }
         }
 
-        self.expect(TCode::PAREN_R);
-        call_tree
+        call_tree.token.loc.last = self.lexer.loc.first.clone();
+        self.expect(TCode::PAREN_R)
+            .map_err(|e| e.with_label(call_tree.token.loc.first.clone(), "call starts here"))?;
+        Ok(call_tree)
     }
 
     // expr = or_expr
-    pub fn parse_expr(&mut self) -> MTree {
+    pub fn parse_expr(&mut self) -> Result<MTree> {
         // println!("parse_expr {:?}", self.curr());
         self.parse_or_expr()
     }
 
-    fn parse_or_expr(&mut self) -> MTree {
+    fn parse_or_expr(&mut self) -> Result<MTree> {
         // println!("parse_or_expr {:?}", self.curr());
-        let mut left = self.parse_and_expr();
-        while self.accept(TCode::OR) {
-            let mut node = MTree::new(Token::from(TCode::OR));
+        let mut left = self.parse_and_expr()?;
+        while self.peek(TCode::OR) {
+            let mut node = MTree::new(self.curr_token());
+            self.advance()?;
             node._push(left);
-            node._push(self.parse_and_expr());
+            node._push(self.parse_and_expr()?);
             left = node;
         }
-        left
+        Ok(left)
     }
 
-    fn parse_and_expr(&mut self) -> MTree {
+    fn parse_and_expr(&mut self) -> Result<MTree> {
         // println!("parse_and_expr {:?}", self.curr());
-        let mut left = self.parse_rel_expr();
-        while self.accept(TCode::AND) {
-            let mut node = MTree::new(Token::from(TCode::AND));
+        let mut left = self.parse_rel_expr()?;
+        while self.peek(TCode::AND) {
+            let mut node = MTree::new(self.curr_token());
+            self.advance()?;
             node._push(left);
-            node._push(self.parse_rel_expr());
+            node._push(self.parse_rel_expr()?);
             left = node;
         }
-        left
+        Ok(left)
     }
 
-    fn parse_rel_expr(&mut self) -> MTree {
+    fn parse_rel_expr(&mut self) -> Result<MTree> {
         // println!("parse_rel_expr {:?}", self.curr());
-        let mut left = self.parse_add_expr();
-        while matches!(self.curr(), TCode::LT | TCode::GT | TCode::EQ | TCode::NOT_EQ) {
-            let op = self.curr();
-            self.advance();
-            let mut node = MTree::new(Token::from(op));
+        let mut left = self.parse_add_expr()?;
+        while matches!(self.curr(), TCode::LT | TCode::GT | TCode::LE | TCode::GE | TCode::EQ | TCode::NOT_EQ) {
+            let op = self.curr_token();
+            self.advance()?;
+            let mut node = MTree::new(op);
             node._push(left);
-            node._push(self.parse_add_expr());
+            node._push(self.parse_add_expr()?);
             left = node;
         }
-        left
+        Ok(left)
     }
 
-    fn parse_add_expr(&mut self) -> MTree {
+    fn parse_add_expr(&mut self) -> Result<MTree> {
         // println!("parse_add_expr {:?}", self.curr());
-        let mut left = self.parse_mul_expr();
+        let mut left = self.parse_mul_expr()?;
         while matches!(self.curr(), TCode::ADD | TCode::SUB) {
-            let op = self.curr();
-            self.advance();
-            let mut node = MTree::new(Token::from(op));
+            let op = self.curr_token();
+            self.advance()?;
+            let mut node = MTree::new(op);
             node._push(left);
-            node._push(self.parse_mul_expr());
+            node._push(self.parse_mul_expr()?);
             left = node;
         }
-        left
+        Ok(left)
     }
 
-    fn parse_mul_expr(&mut self) -> MTree {
+    fn parse_mul_expr(&mut self) -> Result<MTree> {
         // println!("parse_mul_expr {:?}", self.curr());
-        let mut left = self.parse_unary_expr();
-        while matches!(self.curr(), TCode::MULT | TCode::DIV) {
-            let op = self.curr();
-            self.advance();
-            let mut node = MTree::new(Token::from(op));
+        let mut left = self.parse_unary_expr()?;
+        while matches!(self.curr(), TCode::MULT | TCode::DIV | TCode::MOD) {
+            let op = self.curr_token();
+            self.advance()?;
+            let mut node = MTree::new(op);
             node._push(left);
-            node._push(self.parse_unary_expr());
+            node._push(self.parse_unary_expr()?);
             left = node;
         }
-        left
+        Ok(left)
     }
 
-    fn parse_unary_expr(&mut self) -> MTree {
+    fn parse_unary_expr(&mut self) -> Result<MTree> {
         // println!("parse_unary_expr {:?}", self.curr());
+        if self.peek(TCode::SUB) && let Some(literal) = self.parse_min_literal()? {
+            return Ok(literal);
+        }
         if matches!(self.curr(), TCode::NOT | TCode::SUB) {
-            let op = self.curr();
-            self.advance();
-            let mut node = MTree::new(Token::from(op));
-            node._push(self.parse_unary_expr());
-            node
+            let op = self.curr_token();
+            self.advance()?;
+            let mut node = MTree::new(op);
+            node._push(self.parse_unary_expr()?);
+            Ok(node)
         } else {
-            self.parse_primary()
+            self.parse_pow_expr()
+        }
+    }
+
+    /// -9223372036854775808 (I64::MIN) as literal, as its magnitude is out of range for I64:
+    /// the lexer gives magnitude 2^63 behind SUB as I64::MIN, which is taken as is here
+    /// (unless it is the base of POW); anything else is left for parse_unary_expr()
+    fn parse_min_literal(&mut self) -> Result<Option<MTree>> {
+        let saved_lexer = self.lexer.clone();
+        let pos_sub = self.lexer.loc.first.clone();
+        self.advance()?;
+        if matches!(self.curr(), TCode::VAL(DValue::I64(i64::MIN))) {
+            let mut literal = self.curr_token();
+            self.advance()?;
+            if !self.peek(TCode::POW) {
+                let pos_val = &literal.loc.first;
+                if pos_val.row == pos_sub.row {
+                    let len = pos_val.col + pos_val.len - pos_sub.col;
+                    literal.loc = TLoc::at(TPos::new(pos_sub.row, pos_sub.col, len));
+                }
+                return Ok(Some(MTree::new(literal)));
+            }
         }
+        self.lexer = saved_lexer;
+        Ok(None)
     }
 
-    fn parse_primary(&mut self) -> MTree {
+    // pow_expr = primary [ POW unary_expr ]   (right associative, binds tighter than unary minus)
+    fn parse_pow_expr(&mut self) -> Result<MTree> {
+        let left = self.parse_primary()?;
+        if self.peek(TCode::POW) {
+            let mut node = MTree::new(self.curr_token());
+            self.advance()?;
+            node._push(left);
+            node._push(self.parse_unary_expr()?);
+            Ok(node)
+        } else {
+            Ok(left)
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<MTree> {
         // println!("parse_primary {:?}", self.curr());
         match self.curr() {
             TCode::ID(_) => {
                 let saved = self.lexer.clone();
-                let id = self.curr();
-                self.advance();
+                let id = self.curr_token();
+                self.advance()?;
 
                 if self.peek(TCode::PAREN_L) {
                     self.lexer = saved;
@@ -460,24 +711,134 @@ impl Parser {
                 } else {
                     // let id = self.curr();
                     // self.advance();
-                    MTree::new(Token::from(id))
+                    Ok(MTree::new(id))
                 }
             }
 
+            // magnitude of I64::MIN without unary minus, see parse_min_literal()
+            TCode::VAL(DValue::I64(i64::MIN)) => {
+                Err(Error::lex("Integer literal of magnitude 9223372036854775808 is out of range for I64")
+                    .at(self.lexer.loc.clone()))
+            }
+
             TCode::VAL(_) => {
-                let val = self.curr();
-                self.advance();
-                MTree::new(Token::from(val))
+                let val = self.curr_token();
+                self.advance()?;
+                Ok(MTree::new(val))
             }
 
             TCode::PAREN_L => {
-                self.advance();
-                let expr = self.parse_expr();
-                self.expect(TCode::PAREN_R);
-                expr
+                let pos_paren = self.lexer.loc.first.clone();
+                self.advance()?;
+                let expr = self.parse_expr()?;
+                self.expect(TCode::PAREN_R)
+                    .map_err(|e| e.with_label(pos_paren, "parenthesis opened here"))?;
+                Ok(expr)
             }
 
-            _ => panic!("Unexpected primary: {:?}", self.curr()),
+            _ => Err(self.error(format!("Unexpected primary: {:?}", self.curr()))),
         }
     }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use crate::hw_assignment_3::Lexer;
+    use crate::token::TCode;
+    use super::Parser;
+
+    /// codes of top level items and messages of syntax errors
+    fn parse_items(source: &str) -> (Vec<String>, Vec<String>) {
+        let Ok(lexer) = source.parse::<Lexer>();
+        let mut parser = Parser::new(lexer);
+        let tree = parser.analyze().unwrap();
+        let items = tree.children.iter().map(|item| format!("{:?}", item.token.code)).collect();
+        let errors = parser.errors.iter().map(|e| format!("{}:{} {}", e.loc.first.row, e.loc.first.col, e.msg)).collect();
+        (items, errors)
+    }
+
+    /// codes of statements in block of first function and positions of syntax errors
+    fn parse_body(source: &str) -> (Vec<TCode>, Vec<(usize, usize)>) {
+        let Ok(lexer) = source.parse::<Lexer>();
+        let mut parser = Parser::new(lexer);
+        let tree = parser.analyze().unwrap();
+        let block = &tree.children[0].children[2];
+        let stmts = block.children.iter().map(|stmt| stmt.token.code.clone()).collect();
+        let errors = parser.errors.iter().map(|e| (e.loc.first.row, e.loc.first.col)).collect();
+        (stmts, errors)
+    }
+
+    #[test]
+    fn report_all_errors_in_function_body() {
+        let source = "func main()\n[\n    let x = ;\n    write 1 +;\n    let y = 2;\n    z 3;\n    write y;\n]";
+        let (stmts, errors) = parse_body(source);
+        assert_eq!(errors, [(3, 13), (4, 14), (6, 7)]);
+        // parsing resumes at statement following the broken one
+        assert_eq!(stmts, [TCode::ERROR, TCode::ERROR, TCode::LET, TCode::ERROR, TCode::WRITE]);
+    }
+
+    #[test]
+    fn recover_inside_nested_block() {
+        let source = "func main()\n[\n    if true [\n        write ;\n        write 1;\n    ]\n    write (2;\n    write 3;\n]";
+        let (stmts, errors) = parse_body(source);
+        assert_eq!(errors, [(4, 15), (7, 13)]);
+        assert_eq!(stmts, [TCode::IF, TCode::ERROR, TCode::WRITE]);
+    }
+
+    #[test]
+    fn negated_literal_of_i64_min() {
+        let (stmts, errors) = parse_body("func main() [ let a = -9223372036854775808; let b = - -9223372036854775808; ]");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(stmts, [TCode::LET, TCode::LET]);
+    }
+
+    #[test]
+    fn literal_of_magnitude_of_i64_min_without_unary_minus_is_error() {
+        let (_, errors) = parse_body("func main() [ let a = 1 - 9223372036854775808; let b = -9223372036854775808 ** 2; ]");
+        assert_eq!(errors, [(1, 27), (1, 57)]);
+    }
+
+    #[test]
+    fn lex_error_of_first_token_is_recorded() {
+        let Ok(lexer) = "$ func main() [ ]".parse::<Lexer>();
+        let mut parser = Parser::new(lexer);
+        assert_eq!(parser.errors.len(), 1);
+        parser.recover = false;
+        let error = parser.analyze().unwrap_err();
+        assert_eq!(error.kind, ErrorKind::LEX);
+        assert_eq!((error.loc.first.row, error.loc.first.col), (1, 1));
+    }
+
+    #[test]
+    fn global_declarations_and_functions() {
+        let (items, errors) = parse_items("const K = 1;\nlet mut n = K;\nfunc main() [ ]");
+        assert!(errors.is_empty());
+        assert_eq!(items, ["CONST", "MUT", "FUNC", "CALL"]);
+    }
+
+    #[test]
+    fn const_requires_initializer() {
+        let (_, errors) = parse_items("func main() [ const c; let p; ]");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("1:22 "), "{:?}", errors);
+    }
+
+    #[test]
+    fn recover_behind_broken_global_declaration() {
+        let source = "let z = ;\nconst W = 3;\nfunc b(\nfunc main() [ ]";
+        let (items, errors) = parse_items(source);
+        assert_eq!(items, ["ERROR", "CONST", "ERROR", "FUNC", "CALL"]);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("1:9 "));
+        assert!(errors[1].starts_with("4:1 "));
+    }
+
+    #[test]
+    fn recover_at_next_item_after_global_declaration() {
+        let (items, errors) = parse_items("let x = 1\nlet y = 2;\nfunc main() [ ]");
+        assert_eq!(items, ["ERROR", "LET", "FUNC", "CALL"]);
+        assert_eq!(errors.len(), 1);
+    }
 }
\ No newline at end of file
@@ -1,19 +1,22 @@
 #![allow(non_snake_case)]
 
-use std::env::args;
-use std::fs::read_to_string;
+use std::fs::{read_to_string, File};
 use std::ops::Deref;
 use std::rc::Rc;
 use crate::analyzer::Analyzer;
+use crate::diagnostic::{Diagnostics, Source};
+use crate::error::Result;
 use crate::evaluator::Evaluator;
 use crate::mtree::MTree;
 use crate::token::{Token, TCode};
-use crate::value::{DValue};
+use crate::value::{DValue, Overflow};
 use crate::hw_assignment_3::Lexer;
 use crate::hw_assignment_4::Parser;
-use std::io::{self, Write};
+use std::io::{self, BufReader, Write};
 
 mod token;
+mod error;
+mod diagnostic;
 mod mtree;
 mod log;
 mod analyzer;
@@ -33,9 +36,14 @@ fn main() {
         io::stdout().flush().unwrap();
         let mut input = String::new();
 
-        if io::stdin().read_line(&mut input).is_err() {
-            println!("Failed to read input.");
-            continue;
+        match io::stdin().read_line(&mut input) {
+            // end of input (such as a closed pipe) ends the session
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(_) => {
+                println!("Failed to read input.");
+                continue;
+            }
         }
 
         let collected: Vec<&str> = input.trim().split_whitespace().collect();
@@ -75,72 +83,36 @@ fn main() {
                     println!("Too many arguments!");
                 }
             }
+            "lex" => {
+                let code = input.trim_start()[collected[0].len()..].trim();
+                if code.is_empty() { println!("Code must be given"); continue; }
+                lex_snippet(code);
+            },
             "tokenize" => {
-                if args.is_empty() { println!("File must be given") }
-                let file_path = args[0];
-                println!("Running tokenization of file {}: ", file_path);
-
-                let mut lexer = Lexer::new();
-                lexer.set_input(file_path.clone().parse().unwrap());
-                RunLexerOnFile(&mut lexer);
+                if args.is_empty() { println!("File must be given"); continue; }
+                run_file(args[0], tokenize_file);
             },
             "parse" => {
-                if args.is_empty() { println!("File must be given") }
-                let file_path = &args[0];
-                println!("Running parser to tokenize & parse a file {}:", file_path);
-
-                // create recursive descent parser
-                let mut lexer = Lexer::new();
-                lexer.set_input(String::from(file_path.clone()));
-                let mut parser = Parser::new(lexer);
-
-                // start recursive descent parsing
-                let tree = parser.analyze();
-
-                println!("\nMTree:");
-                tree.print();
+                if args.is_empty() { println!("File must be given"); continue; }
+                run_file(args[0], parse_file);
             },
             "execute" => {
                 println!("Execute given file.");
 
-                if args.is_empty() { println!("File must be given") }
-                let file_path = &args[0];
-                println!("Running parser to tokenize & parse a file {}:", file_path);
-
-                // create recursive descent parser
-                let mut lexer = Lexer::new();
-                lexer.set_input(String::from(file_path.clone()));
-                let mut parser = Parser::new(lexer);
-
-                // start recursive descent parsing
-                let tree = parser.analyze();
-
-                println!("------------------------PARSER----------------------------------------");
-                println!("\nMTree:");
-                tree.print();
-
-                // --------------------------------------------------------
-                // analyze tree
-                // --------------------------------------------------------
-                println!("------------------------ANALYZER----------------------------------------");
-                let analyzer = Analyzer::new();
-                let rc_tree_analyzed = analyzer.analyze_global(Rc::new(tree.clone()));
-                println!("\nMTree (Analyzed) 'global':\n");
-                rc_tree_analyzed.print();
-
-                // --------------------------------------------------------
-                // evaluate tree
-                // --------------------------------------------------------
-                println!("----------------------------EVALUATE------------------------------------");
-                println!("\nEVALUATE MTree (Analyzed) 'global' :\n");
+                if args.is_empty() { println!("File must be given"); continue; }
                 let mut evaluator = Evaluator::new();
-                evaluator.evaluate(rc_tree_analyzed.deref());
+                if !configure_evaluator(&mut evaluator, &args[1..]) {
+                    continue;
+                }
+                run_file(args[0], |lexer, diagnostics| {
+                    execute_file(lexer, &mut evaluator, diagnostics)
+                });
             }
             "example" => {
-                if args.is_empty() { println!("Example choice not given.") }
+                if args.is_empty() { println!("Example choice not given."); continue; }
                 let example_choice = args[0];
 
-                match example_choice {
+                let result = match example_choice {
                     "OHL" => {
                         ohl_analyzer_evaluator_sample_function()
                     }
@@ -148,8 +120,12 @@ fn main() {
                         yarrick_analyzer_evaluator_sample_function()
                     }
                     _ => {
-                        println!("Invalid example choice given.")
+                        println!("Invalid example choice given.");
+                        Ok(())
                     }
+                };
+                if let Err(e) = result {
+                    println!("{}", e);
                 }
             }
             _ => println!("Unknown command: {}", cmd)
@@ -159,7 +135,94 @@ fn main() {
     return;
 }
 
-fn ohl_analyzer_evaluator_sample_function() {
+/// run a command on a source file, errors are rendered as diagnostics against the file
+fn run_file<F>(file_path: &str, run: F)
+    where F: FnOnce(Lexer, &mut Diagnostics) -> Result<()>
+{
+    let mut lexer = Lexer::new();
+    if let Err(e) = lexer.set_input(String::from(file_path)) {
+        println!("{}", e);
+        return;
+    }
+
+    let source = Source::new(file_path, lexer.input_string.clone());
+    let mut diagnostics = Diagnostics::new(source);
+    if let Err(e) = run(lexer, &mut diagnostics) {
+        diagnostics.report_error(&e);
+    }
+    diagnostics.flush();
+}
+
+/// print tokens of code given on the command line, such as: lex x = 'a' + 1;
+fn lex_snippet(code: &str) {
+    let Ok(lexer) = code.parse::<Lexer>();
+    for token in lexer {
+        println!("{}:{} {:?}", token.loc.first.row, token.loc.first.col, token.code);
+    }
+}
+
+fn tokenize_file(mut lexer: Lexer, diagnostics: &mut Diagnostics) -> Result<()> {
+    println!("Running tokenization of file {}: ", diagnostics.source.name);
+
+    RunLexerOnFile(&mut lexer)
+}
+
+fn parse_file(lexer: Lexer, diagnostics: &mut Diagnostics) -> Result<()> {
+    println!("Running parser to tokenize & parse a file {}:", diagnostics.source.name);
+
+    // create recursive descent parser
+    let mut parser = Parser::new(lexer);
+
+    // start recursive descent parsing
+    let tree = parser.analyze()?;
+
+    println!("\nMTree:");
+    tree.print();
+    for e in &parser.errors {
+        diagnostics.report_error(e);
+    }
+    Ok(())
+}
+
+fn execute_file(lexer: Lexer, evaluator: &mut Evaluator, diagnostics: &mut Diagnostics) -> Result<()> {
+    println!("Running parser to tokenize & parse a file {}:", diagnostics.source.name);
+
+    // create recursive descent parser
+    let mut parser = Parser::new(lexer);
+
+    // start recursive descent parsing
+    let tree = parser.analyze()?;
+
+    println!("------------------------PARSER----------------------------------------");
+    println!("\nMTree:");
+    tree.print();
+    if !parser.errors.is_empty() {
+        for e in &parser.errors {
+            diagnostics.report_error(e);
+        }
+        diagnostics.flush();
+        println!("Aborting due to {} syntax error(s).", parser.errors.len());
+        return Ok(());
+    }
+
+    // --------------------------------------------------------
+    // analyze tree
+    // --------------------------------------------------------
+    println!("------------------------ANALYZER----------------------------------------");
+    let analyzer = Analyzer::new();
+    let rc_tree_analyzed = analyzer.analyze_global(Rc::new(tree.clone()))?;
+    println!("\nMTree (Analyzed) 'global':\n");
+    rc_tree_analyzed.print();
+
+    // --------------------------------------------------------
+    // evaluate tree
+    // --------------------------------------------------------
+    println!("----------------------------EVALUATE------------------------------------");
+    println!("\nEVALUATE MTree (Analyzed) 'global' :\n");
+    evaluator.evaluate(rc_tree_analyzed.deref())
+}
+
+fn ohl_analyzer_evaluator_sample_function() -> Result<()> {
     // --------------------------------------------------------
     // Example Program
     // --------------------------------------------------------
@@ -176,7 +239,7 @@ fn ohl_analyzer_evaluator_sample_function() {
     //
     // func main()
     // [
-    //     n = fac(3);
+    //     let n = fac(3);
     //     write n;
     // ]
 
@@ -278,7 +341,7 @@ fn ohl_analyzer_evaluator_sample_function() {
         token: Token::from( TCode::BLOCK),
         children: vec![
             Rc::new( MTree {
-                token: Token::from(TCode::ASSIGN),
+                token: Token::from(TCode::LET),
                 children: vec![
                     Rc::new(MTree::new(Token::id("n")) ),
                     Rc::new( MTree {
@@ -342,7 +405,7 @@ fn ohl_analyzer_evaluator_sample_function() {
     // --------------------------------------------------------
     println!("----------------------------------------------------------------");
     let analyzer = Analyzer::new();
-    let rc_tree_analyzed = analyzer.analyze_global(rc_mtree_global.clone());
+    let rc_tree_analyzed = analyzer.analyze_global(rc_mtree_global.clone())?;
     println!("\nMTree (Analyzed) 'global':\n");
     rc_tree_analyzed.print();
 
@@ -354,10 +417,10 @@ fn ohl_analyzer_evaluator_sample_function() {
     println!("----------------------------------------------------------------");
     println!("\nEVALUATE MTree (Analyzed) 'global' :\n");
     let mut evaluator = Evaluator::new();
-    evaluator.evaluate(rc_tree_analyzed.deref());
+    evaluator.evaluate(rc_tree_analyzed.deref())
 }
 
-fn yarrick_analyzer_evaluator_sample_function() {
+fn yarrick_analyzer_evaluator_sample_function() -> Result<()> {
     /*
         --------------------------------------------------------
         EXAMPLE PROGRAM - 2.
@@ -378,7 +441,7 @@ fn yarrick_analyzer_evaluator_sample_function() {
         token: Token::from( TCode::BLOCK),
         children: vec![
             Rc::new( MTree {
-                token: Token::from(TCode::ASSIGN),
+                token: Token::from(TCode::LET),
                 children: vec![
                     Rc::new(MTree::new(Token::id("n")) ),
                     Rc::new( MTree::new(Token::from(TCode::VAL(DValue::I64(0))))),
@@ -470,7 +533,7 @@ fn yarrick_analyzer_evaluator_sample_function() {
     // --------------------------------------------------------
     println!("----------------------------------------------------------------");
     let analyzer = Analyzer::new();
-    let rc_tree_analyzed = analyzer.analyze_global(rc_mtree_global.clone());
+    let rc_tree_analyzed = analyzer.analyze_global(rc_mtree_global.clone())?;
     println!("\nMTree (Analyzed) 'global':\n");
     rc_tree_analyzed.print();
 
@@ -482,7 +545,7 @@ fn yarrick_analyzer_evaluator_sample_function() {
     println!("----------------------------------------------------------------");
     println!("\nEVALUATE MTree (Analyzed) 'global' :\n");
     let mut evaluator = Evaluator::new();
-    evaluator.evaluate(rc_tree_analyzed.deref());
+    evaluator.evaluate(rc_tree_analyzed.deref())
 }
 
 fn print_help_for(command: &str) {
@@ -496,6 +559,9 @@ fn print_help_for(command: &str) {
         "list" => {
             println!("list commands OR list tokens: \n- prints the list of all commands with or without the second field being entered as [commands]\n- if the 2nd field is [tokens], will print out all tokens in the token enum\n");
         }
+        "lex" => {
+            println!("lex <code>: \n- tokenizes the code given on the command line and prints every token with its row and column\n");
+        }
         "tokenize" => {
             println!("tokenize <file>: \n- tokenizes the input from a file and then prints out the token form of the function\n");
         }
@@ -503,7 +569,7 @@ fn print_help_for(command: &str) {
             println!("parse <file>: \n- tokenizes the input from the file and then parses the tokens.\n- Prints out the tree form of the function.\n");
         }
         "execute" => {
-            println!("execute <file>: \n- The whole shebang.\n- tokenizes, parses, analyzes, and then executes the given file.\n- Prints output for each step.");
+            println!("execute <file> [--input <file>] [--output <file>] [--no-prefix] [--overflow <mode>]: \n- The whole shebang.\n- tokenizes, parses, analyzes, and then executes the given file.\n- Prints output for each step.\n- Values for 'read' are taken from the input file if given, otherwise from stdin.\n- Values of 'write' go to the output file if given, otherwise to stdout (without \"> \" if --no-prefix is given).\n- Integer overflow is an error, unless mode is wrap, saturate or promote (to a float).");
         }
         "example" => {
             println!("example <\"OHL\" | \"YARRICK\">: \n-prints one of two examples that utilize the analyzer and executor on a predefined tree.");
@@ -515,7 +581,7 @@ fn print_help_for(command: &str) {
 }
 
 fn list_command(command: &str) {
-    let print_all_string = String::from("All commands:\n\t help \n\t print \n\t list [commands] \n\t list tokens \n\t example \n\t tokenize \n\t parse \n\t execute \n\t example");
+    let print_all_string = String::from("All commands:\n\t help \n\t print \n\t list [commands] \n\t list tokens \n\t example \n\t lex \n\t tokenize \n\t parse \n\t execute \n\t example");
     match command {
         "commands" => {
             println!("{}", print_all_string);
@@ -526,11 +592,12 @@ fn list_command(command: &str) {
                     Id and Value atoms: ID(String), VAL(DValue)\n
                     Assignment operator: ASSIGN\n
                     Logical operators: NOT, AND, OR\n
-                    Relational operators: LT, GT, EQ, NOT_EQ\n
-                    Arithmetic operators: ADD, SUB, MULT, DIV\n
+                    Relational operators: LT, GT, LE, GE, EQ, NOT_EQ\n
+                    Arithmetic operators: ADD, SUB, MULT, DIV, MOD, POW\n
                     Nesting: PAREN_L, PAREN_R, BRACE_L, BRACE_R,\n
                     Separators: COMMA, SEMICOLON\n
-                    Keywords: FUNC, LET, IF, ELSE, WHILE, RETURN, READ, WRITE,\n
+                    Range operators: DOT_DOT, DOT_DOT_EQ\n
+                    Keywords: FUNC, LET, MUT, CONST, IF, ELSE, WHILE, FOR, IN, RETURN, BREAK, CONTINUE, READ, WRITE,\n
                     Meta-tokens: BLOCK, PARAMS, CALL\n");
         }
         _ => {
@@ -539,27 +606,83 @@ fn list_command(command: &str) {
     }
 }
 
-fn RunLexerOnFile(lex: &mut Lexer) {
-    lex.advance();
-    lex.print_token();
+/// apply options of 'execute' command, returns false on invalid options
+fn configure_evaluator(evaluator: &mut Evaluator, options: &[&str]) -> bool {
+    let mut idx = 0;
+    while idx < options.len() {
+        match options[idx] {
+            "--input" | "--output" if idx + 1 < options.len() => {
+                let file_path = options[idx + 1];
+                let result = if options[idx] == "--input" {
+                    File::open(file_path).map(|file| evaluator.set_input(BufReader::new(file)))
+                } else {
+                    File::create(file_path).map(|file| evaluator.set_output(file))
+                };
+                if let Err(e) = result {
+                    println!("Can't open file {}: {}", file_path, e);
+                    return false;
+                }
+                idx += 2;
+            }
+            "--no-prefix" => {
+                evaluator.write_prefix = false;
+                idx += 1;
+            }
+            "--overflow" if idx + 1 < options.len() => {
+                match Overflow::from_name(options[idx + 1]) {
+                    Some(overflow) => { evaluator.overflow = overflow; }
+                    None => {
+                        println!("Invalid overflow mode: {} (expected error, wrap, saturate or promote)", options[idx + 1]);
+                        return false;
+                    }
+                }
+                idx += 2;
+            }
+            option => {
+                println!("Invalid option: {}", option);
+                return false;
+            }
+        }
+    }
+    true
+}
 
-    while lex.token.clone().unwrap() != TCode::EOI {
-        lex.advance();
+fn RunLexerOnFile(lex: &mut Lexer) -> Result<()> {
+    // tokens of one row are printed on one line
+    let mut row = 1;
+    loop {
+        lex.advance()?;
+        while row < lex.loc.first.row {
+            println!();
+            row += 1;
+        }
         lex.print_token();
+        if lex.token.clone().unwrap() == TCode::EOI {
+            break;
+        }
     }
+    Ok(())
 }
 
 fn print(file_name: String, flag : String) {
+    let content = match read_to_string(&file_name) {
+        Ok(content) => { content }
+        Err(e) => {
+            println!("Can't read file {}: {}", file_name, e);
+            return;
+        }
+    };
+
     if flag == "--numbered" {
         let mut count : i16 = 1;
 
-        for line in read_to_string(file_name).unwrap().lines() {
+        for line in content.lines() {
             println!("{} {}", count, line.to_string());
             count += 1;
         }
     }
     else {
-        for line in read_to_string(file_name).unwrap().lines() {
+        for line in content.lines() {
             println!("{}", line.to_string());
         }
     }
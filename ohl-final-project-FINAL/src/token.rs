@@ -29,6 +29,8 @@ pub enum TCode {
     // relational operators
     LT,          // less than
     GT,          // greater than
+    LE,          // less than or equal
+    GE,          // greater than or equal
     EQ,       // equal
     NOT_EQ,   // not equal
 
@@ -37,6 +39,8 @@ pub enum TCode {
     SUB,
     MULT,
     DIV,
+    MOD,         // remainder
+    POW,         // power
 
     // nesting
     PAREN_L,
@@ -48,13 +52,23 @@ pub enum TCode {
     COMMA,
     SEMICOLON,
 
+    // range operators
+    DOT_DOT,     // exclusive range a..b
+    DOT_DOT_EQ,  // inclusive range a..=b
+
     // keywords
     FUNC,
-    LET,
+    LET,        // variable (also node of declaration)
+    MUT,        // optional after let (also node of declaration with let mut)
+    CONST,      // immutable variable (also node of declaration)
     IF,
     ELSE,
     WHILE,
+    FOR,
+    IN,
     RETURN,
+    BREAK,
+    CONTINUE,
     READ,
     WRITE,
 
@@ -81,23 +95,35 @@ impl fmt::Debug for TCode {
             TCode::NOT => write!(f, "NOT"),
             TCode::LT => write!(f, "LT"),
             TCode::GT => write!(f, "GT"),
+            TCode::LE => write!(f, "LE"),
+            TCode::GE => write!(f, "GE"),
             TCode::EQ => write!(f, "EQ"),
             TCode::NOT_EQ => write!(f, "NOT_EQ"),
             TCode::ADD => write!(f, "ADD"),
             TCode::SUB => write!(f, "SUB"),
             TCode::MULT => write!(f, "MULT"),
             TCode::DIV => write!(f, "DIV"),
+            TCode::MOD => write!(f, "MOD"),
+            TCode::POW => write!(f, "POW"),
             TCode::PAREN_L => write!(f, "PAREN_L"),
             TCode::PAREN_R => write!(f, "PAREN_R"),
             TCode::BRACE_L => write!(f, "BRACE_L"),
             TCode::BRACE_R => write!(f, "BRACE_R"),
             TCode::COMMA => write!(f, "COMMA"),
             TCode::SEMICOLON => write!(f, "SEMICOLON"),
+            TCode::DOT_DOT => write!(f, "DOT_DOT"),
+            TCode::DOT_DOT_EQ => write!(f, "DOT_DOT_EQ"),
             TCode::LET => write!(f, "LET"),
+            TCode::MUT => write!(f, "MUT"),
+            TCode::CONST => write!(f, "CONST"),
             TCode::IF => write!(f, "ID"),
             TCode::ELSE => write!(f, "ELSE"),
             TCode::WHILE => write!(f, "WHILE"),
+            TCode::FOR => write!(f, "FOR"),
+            TCode::IN => write!(f, "IN"),
             TCode::RETURN => write!(f, "RET"),
+            TCode::BREAK => write!(f, "BREAK"),
+            TCode::CONTINUE => write!(f, "CONTINUE"),
             TCode::READ => write!(f, "READ"),
             TCode::WRITE => write!(f, "WRITE"),
             TCode::PARAMS => write!(f, "PARAMS"),
@@ -123,9 +149,14 @@ pub struct TPos {
 }
 
 impl TPos {
-    fn new(row: usize, col: usize, len: usize) -> TPos {
+    pub fn new(row: usize, col: usize, len: usize) -> TPos {
         TPos { row, col, len }
     }
+
+    /// position is unknown (such as for generated tokens)
+    pub fn is_empty(&self) -> bool {
+        self.row == 0
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -141,6 +172,18 @@ impl TLoc {
             last: TPos::new(0, 0, 0),
         }
     }
+
+    /// location of a single token (without corresponding delimiter)
+    pub fn at(pos: TPos) -> TLoc {
+        TLoc {
+            first: pos.clone(),
+            last: pos,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.first.is_empty()
+    }
 }
 
 #[derive(Debug, Clone)]
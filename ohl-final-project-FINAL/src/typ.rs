@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use std::fmt;
+
 /// atomic type
 #[derive(Debug, Copy, Clone)]
 pub enum ATyp {
@@ -8,6 +10,7 @@ pub enum ATyp {
     CHAR,
     I64,
     F64,
+    STR,
 }
 
 /// composite type
@@ -69,6 +72,12 @@ impl ATyp {
                     _ => { ATyp::TOK }
                 }
             }
+            ATyp::STR => {
+                match other {
+                    ATyp::STR => { ATyp::STR }
+                    _ => { ATyp::TOK }
+                }
+            }
             _ => { ATyp::TOK }
         }
     }
@@ -77,6 +86,12 @@ impl ATyp {
 
 impl Typ {
 
+    /// type of function with n_params parameters (of dynamic type)
+    pub fn func(n_params: usize) -> Typ {
+        let params = Typ::C(CTyp::TUPLE(vec![Typ::D; n_params]));
+        Typ::C(CTyp::FUNC(Box::new(params), Box::new(Typ::D)))
+    }
+
     pub fn isNumeric(&self) -> bool {
         match self {
             Typ::A(a) => {
@@ -93,4 +108,55 @@ impl Typ {
         }
     }
 
+    /// number of parameters of function type
+    pub fn arity(&self) -> Option<usize> {
+        match self {
+            Typ::C(CTyp::FUNC(params, _)) => {
+                match params.as_ref() {
+                    Typ::C(CTyp::TUPLE(typs)) => { Some(typs.len()) }
+                    _ => { Some(1) }
+                }
+            }
+            _ => { None }
+        }
+    }
+
+}
+
+
+impl fmt::Display for ATyp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ATyp::TOK => write!(f, "TOK"),
+            ATyp::BOOL => write!(f, "BOOL"),
+            ATyp::CHAR => write!(f, "CHAR"),
+            ATyp::I64 => write!(f, "I64"),
+            ATyp::F64 => write!(f, "F64"),
+            ATyp::STR => write!(f, "STR"),
+        }
+    }
+}
+
+
+impl fmt::Display for CTyp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CTyp::FUNC(_, _) => write!(f, "FUNC"),
+            CTyp::TUPLE(_) => write!(f, "TUPLE"),
+            CTyp::LIST(_) => write!(f, "LIST"),
+            CTyp::MAP(_, _) => write!(f, "MAP"),
+        }
+    }
+}
+
+
+/// name of type for messages, such as I64 or FUNC (instead of A(I64) or C(FUNC(..)))
+impl fmt::Display for Typ {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Typ::A(a) => write!(f, "{}", a),
+            Typ::C(c) => write!(f, "{}", c),
+            Typ::D => write!(f, "DYN"),
+        }
+    }
 }
\ No newline at end of file
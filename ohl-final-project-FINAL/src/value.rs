@@ -1,5 +1,8 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
 use std::rc::Rc;
+use crate::error::{Error, Result};
 use crate::mtree::MTree;
 use crate::token::TCode;
 use crate::typ::{ATyp, CTyp, Typ};
@@ -19,6 +22,8 @@ impl TCode {
         match self {
             TCode::LT => { true }
             TCode::GT => { true }
+            TCode::LE => { true }
+            TCode::GE => { true }
             TCode::EQ => { true }
             TCode::NOT_EQ => { true }
             _ => { false }
@@ -31,6 +36,8 @@ impl TCode {
             TCode::SUB => { true }
             TCode::MULT => { true }
             TCode::DIV => { true }
+            TCode::MOD => { true }
+            TCode::POW => { true }
             _ => { false }
         }
     }
@@ -41,6 +48,127 @@ impl TCode {
 }
 
 
+/// behavior of I64 arithmetic if the result is out of range
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Overflow {
+    ERROR,      // runtime error
+    WRAP,       // two's complement wrap around
+    SATURATE,   // clamp to I64::MIN or I64::MAX
+    PROMOTE,    // compute result as F64 instead
+}
+
+
+impl Overflow {
+
+    pub fn from_name(name: &str) -> Option<Overflow> {
+        match name {
+            "error" => { Some(Overflow::ERROR) }
+            "wrap" => { Some(Overflow::WRAP) }
+            "saturate" => { Some(Overflow::SATURATE) }
+            "promote" => { Some(Overflow::PROMOTE) }
+            _ => { None }
+        }
+    }
+
+    /// I64 arithmetic
+    ///
+    /// division truncates toward zero and the remainder has the sign of the dividend,
+    /// division by zero and a negative exponent are always errors (as result is no I64)
+    fn intOp(self, code: &TCode, l: i64, r: i64) -> Result<DValue> {
+        if matches!(code, TCode::DIV | TCode::MOD) && r == 0 {
+            return Err(Error::runtime("Division by zero!"));
+        }
+        if matches!(code, TCode::POW) && r < 0 {
+            return Err(Error::runtime(format!("Negative exponent {} of I64 power, use F64 base instead!", r)));
+        }
+        // (I64::MIN % -1 is 0, but I64::MIN / -1 is out of range)
+        if matches!(code, TCode::MOD) {
+            return Ok(DValue::I64(l.wrapping_rem(r)));
+        }
+
+        let checked = match code {
+            TCode::ADD => { l.checked_add(r) }
+            TCode::SUB => { l.checked_sub(r) }
+            TCode::MULT => { l.checked_mul(r) }
+            TCode::DIV => { l.checked_div(r) }
+            TCode::POW => { checked_pow(l, r) }
+            _ => { return Err(Error::runtime(format!("{:?} is not arithmetic operator!", code))) }
+        };
+        if let Some(i) = checked {
+            return Ok(DValue::I64(i));
+        }
+
+        match self {
+            Overflow::ERROR => {
+                Err(Error::runtime(format!("Integer overflow in {:?} of {} and {}!", code, l, r)))
+            }
+            Overflow::WRAP => {
+                match code {
+                    TCode::ADD => { Ok(DValue::I64(l.wrapping_add(r))) }
+                    TCode::SUB => { Ok(DValue::I64(l.wrapping_sub(r))) }
+                    TCode::MULT => { Ok(DValue::I64(l.wrapping_mul(r))) }
+                    TCode::DIV => { Ok(DValue::I64(l.wrapping_div(r))) }
+                    _ => { Ok(DValue::I64(wrapping_pow(l, r))) }
+                }
+            }
+            Overflow::SATURATE => {
+                match code {
+                    TCode::ADD => { Ok(DValue::I64(l.saturating_add(r))) }
+                    TCode::SUB => { Ok(DValue::I64(l.saturating_sub(r))) }
+                    TCode::MULT => { Ok(DValue::I64(l.saturating_mul(r))) }
+                    TCode::DIV => { Ok(DValue::I64(l.saturating_div(r))) }
+                    // (only |l| >= 2 overflows, sign is negative for odd power of negative base)
+                    _ => { Ok(DValue::I64(if l < 0 && r % 2 == 1 { i64::MIN } else { i64::MAX })) }
+                }
+            }
+            Overflow::PROMOTE => { floatOp(code, l as f64, r as f64) }
+        }
+    }
+}
+
+
+/// l ** r for r >= 0 (None on overflow), also for exponents beyond u32
+fn checked_pow(l: i64, r: i64) -> Option<i64> {
+    if let Ok(exp) = u32::try_from(r) {
+        return l.checked_pow(exp);
+    }
+    match l {
+        0 => { Some(0) }
+        1 => { Some(1) }
+        -1 => { Some(if r % 2 == 0 { 1 } else { -1 }) }
+        _ => { None }
+    }
+}
+
+
+/// l ** r for r >= 0 with two's complement wrap around (square and multiply)
+fn wrapping_pow(l: i64, r: i64) -> i64 {
+    let (mut base, mut exp, mut acc) = (l, r, 1_i64);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            acc = acc.wrapping_mul(base);
+        }
+        base = base.wrapping_mul(base);
+        exp >>= 1;
+    }
+    acc
+}
+
+
+/// F64 arithmetic (IEEE 754, such as 1.0 / 0 = inf)
+fn floatOp(code: &TCode, l: f64, r: f64) -> Result<DValue> {
+    match code {
+        TCode::ADD => { Ok(DValue::F64(l + r)) }
+        TCode::SUB => { Ok(DValue::F64(l - r)) }
+        TCode::MULT => { Ok(DValue::F64(l * r)) }
+        TCode::DIV => { Ok(DValue::F64(l / r)) }
+        TCode::MOD => { Ok(DValue::F64(l % r)) }
+        TCode::POW => { Ok(DValue::F64(l.powf(r))) }
+        _ => { Err(Error::runtime(format!("{:?} is not arithmetic operator!", code))) }
+    }
+}
+
+
 /// pair of numeric values of same type
 #[derive(Debug, Clone)]
 enum DNumPair {
@@ -52,7 +180,7 @@ enum DNumPair {
 /// dynamic value of Typ::D
 ///
 /// Typ::D =
-///   | ATyp::TOK | ATyp::BOOL | ATyp::CHAR | ATyp::I64 | ATyp::F64
+///   | ATyp::TOK | ATyp::BOOL | ATyp::CHAR | ATyp::I64 | ATyp::F64 | ATyp::STR
 ///   | CTyp::FUNC (Typ::D -> Typ::D)
 ///   | CTyp::LIST (Tup::D)
 ///   | CTyp::MAP (Typ::D -> Typ::D)
@@ -64,6 +192,7 @@ pub enum DValue {
     CHAR(char),
     I64(i64),
     F64(f64),
+    STR(String),
     FUNC(Rc<MTree>),
     _LIST(Vec<DValue>),
     _MAP(HashMap<DValue, DValue>),
@@ -79,6 +208,7 @@ impl DValue {
             DValue::CHAR(_) => { Typ::A(ATyp::CHAR) }
             DValue::I64(_) => { Typ::A(ATyp::I64) }
             DValue::F64(_) => { Typ::A(ATyp::F64) }
+            DValue::STR(_) => { Typ::A(ATyp::STR) }
             DValue::FUNC(_) => {
                 Typ::C(CTyp::FUNC(Box::new(Typ::D), Box::new(Typ::D)))
             }
@@ -98,223 +228,352 @@ impl DValue {
             DValue::CHAR(c) => { c.to_string() }
             DValue::I64(i) => { i.to_string() }
             DValue::F64(f) => { f.to_string() }
-            DValue::FUNC(_) => { todo!() }  // what to do here?
-            DValue::_LIST(_) => { todo!() }
-            DValue::_MAP(_) => { todo!() }
+            DValue::STR(s) => { s.clone() }
+            DValue::FUNC(_) => { String::from("<func>") }
+            DValue::_LIST(values) => {
+                let items: Vec<String> = values.iter().map(|v| v.toString()).collect();
+                format!("[{}]", items.join(", "))
+            }
+            DValue::_MAP(map) => {
+                let items: Vec<String> = map.iter()
+                    .map(|(k, v)| format!("{}: {}", k.toString(), v.toString()))
+                    .collect();
+                format!("{{{}}}", items.join(", "))
+            }
         }
     }
 
-    pub fn cast(&self, typ: ATyp) -> DValue {
+    /// parse a line of (user) input into a value
+    ///
+    ///   BOOL: true | false
+    ///   I64:  integer, such as -42
+    ///   F64:  floating point number, such as 3.14
+    ///   CHAR: any other single character
+    ///   STR:  anything else (without line break)
+    ///
+    pub fn from_input(line: &str) -> DValue {
+        let line = line.trim_end_matches(['\n', '\r']);
+        let text = line.trim();
+        match text {
+            "true" => { return DValue::BOOL(true) }
+            "false" => { return DValue::BOOL(false) }
+            _ => {}
+        }
+        if let Ok(i) = text.parse::<i64>() {
+            return DValue::I64(i);
+        }
+        if let Ok(f) = text.parse::<f64>() {
+            return DValue::F64(f);
+        }
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => { DValue::CHAR(c) }
+            _ => { DValue::STR(String::from(line)) }
+        }
+    }
+
+    fn cast_error(&self, typ: ATyp) -> Error {
+        Error::runtime(format!("Can't cast value {} to type {}", self, typ))
+    }
+
+    pub fn cast(&self, typ: ATyp) -> Result<DValue> {
         match self {
             DValue::TOK => {
                 match typ {
-                    ATyp::TOK => { self.clone() }
-                    ATyp::BOOL => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::CHAR => { DValue::CHAR('●') }
-                    ATyp::I64 => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::F64 => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
+                    ATyp::TOK => { Ok(self.clone()) }
+                    ATyp::CHAR => { Ok(DValue::CHAR('●')) }
+                    ATyp::STR => { Ok(DValue::STR(self.toString())) }
+                    _ => { Err(self.cast_error(typ)) }
                 }
             }
             DValue::BOOL(b) => {
                 match typ {
-                    ATyp::TOK => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::BOOL => { self.clone() }
-                    ATyp::CHAR => { DValue::CHAR(if *b { '⊤' } else { '⊥' }) }
-                    ATyp::I64 => { DValue::I64(if *b { 1_i64 } else { 0_i64 }) }
-                    ATyp::F64 => { DValue::F64(if *b { 1_f64 } else { 0_f64 }) }
+                    ATyp::TOK => { Err(self.cast_error(typ)) }
+                    ATyp::BOOL => { Ok(self.clone()) }
+                    ATyp::CHAR => { Ok(DValue::CHAR(if *b { '⊤' } else { '⊥' })) }
+                    ATyp::I64 => { Ok(DValue::I64(if *b { 1_i64 } else { 0_i64 })) }
+                    ATyp::F64 => { Ok(DValue::F64(if *b { 1_f64 } else { 0_f64 })) }
+                    ATyp::STR => { Ok(DValue::STR(self.toString())) }
                 }
             }
             DValue::CHAR(c) => {
                 match typ {
-                    ATyp::TOK => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::BOOL => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::CHAR => { self.clone() }
-                    ATyp::I64 => { DValue::I64(*c as i64) }
-                    ATyp::F64 => { DValue::F64(*c as i64 as f64) }
+                    ATyp::CHAR => { Ok(self.clone()) }
+                    ATyp::I64 => { Ok(DValue::I64(*c as i64)) }
+                    ATyp::F64 => { Ok(DValue::F64(*c as i64 as f64)) }
+                    ATyp::STR => { Ok(DValue::STR(self.toString())) }
+                    _ => { Err(self.cast_error(typ)) }
                 }
             }
             DValue::I64(i) => {
                 match typ {
-                    ATyp::TOK => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::BOOL => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::CHAR => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::I64 => { self.clone() }
-                    ATyp::F64 => { DValue::F64(*i as f64) }
+                    ATyp::I64 => { Ok(self.clone()) }
+                    ATyp::F64 => { Ok(DValue::F64(*i as f64)) }
+                    ATyp::STR => { Ok(DValue::STR(self.toString())) }
+                    _ => { Err(self.cast_error(typ)) }
                 }
             }
             DValue::F64(f) => {
                 match typ {
-                    ATyp::TOK => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::BOOL => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::CHAR => { panic!("Can't cast value {:?} to type {:?}", self, typ); }
-                    ATyp::I64 => { DValue::I64(*f as i64) }
-                    ATyp::F64 => { self.clone() }
+                    ATyp::I64 => { Ok(DValue::I64(*f as i64)) }
+                    ATyp::F64 => { Ok(self.clone()) }
+                    ATyp::STR => { Ok(DValue::STR(self.toString())) }
+                    _ => { Err(self.cast_error(typ)) }
                 }
             }
-            _ => { panic!("Not implemented!") }
+            DValue::STR(s) => {
+                let text = s.trim();
+                let parsed = match typ {
+                    ATyp::TOK => { None }
+                    ATyp::BOOL => { text.parse::<bool>().ok().map(DValue::BOOL) }
+                    ATyp::CHAR => {
+                        let mut chars = s.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) => { Some(DValue::CHAR(c)) }
+                            _ => { None }
+                        }
+                    }
+                    ATyp::I64 => { text.parse::<i64>().ok().map(DValue::I64) }
+                    ATyp::F64 => { text.parse::<f64>().ok().map(DValue::F64) }
+                    ATyp::STR => { Some(self.clone()) }
+                };
+                parsed.ok_or_else(|| self.cast_error(typ))
+            }
+            _ => { Err(self.cast_error(typ)) }
         }
     }
 
-    pub fn unaryOp(&self, code: TCode) -> DValue {
-        match self {
-            DValue::TOK => { panic!("Operator {:?} is undefined on DValue::TOK!", code) }
-            DValue::BOOL(b) => {
-                match code {
-                    TCode::NOT => { DValue::BOOL(! *b) }
-                    _ => { panic!("Operator {:?} is undefined on DValue::Bool!", code) }
-                }
-            }
-            DValue::CHAR(_) => { panic!("Operator {:?} is undefined on DValue::CHAR!", code) }
-            DValue::I64(i) => {
-                match code {
-                    TCode::SUB => { DValue::I64(0 - *i) }
-                    _ => { panic!("Operator {:?} is undefined on DValue::I64!", code) }
-                }
-            }
-            DValue::F64(f) => {
-                match code {
-                    TCode::SUB => { DValue::F64(0.0 - *f) }
-                    TCode::DIV => { DValue::F64(1.0 / *f) }
-                    _ => { panic!("Operator {:?} is undefined on DValue::F64!", code) }
-                }
+    pub fn unaryOp(&self, code: TCode, overflow: Overflow) -> Result<DValue> {
+        match (self, &code) {
+            (DValue::BOOL(b), TCode::NOT) => { Ok(DValue::BOOL(! *b)) }
+            (DValue::I64(i), TCode::SUB) => { overflow.intOp(&code, 0, *i) }
+            (DValue::F64(f), TCode::SUB) => { Ok(DValue::F64(0.0 - *f)) }
+            (DValue::F64(f), TCode::DIV) => { Ok(DValue::F64(1.0 / *f)) }
+            _ => {
+                Err(Error::runtime(format!(
+                    "Operator {:?} is undefined on {}!", code, self.dynamic_typ())))
             }
-            DValue::FUNC(_) => { panic!("Operator {:?} is undefined on DValue::FUNC!", code) }
-            DValue::_LIST(_) => { panic!("Operator {:?} is undefined on DValue::LIST!", code) }
-            DValue::_MAP(_) => { panic!("Operator {:?} is undefined on DValue::MAP!", code) }
         }
     }
 
 
-    pub fn binaryOp(&self, code: TCode, value_rhs : DValue) -> DValue {
+    pub fn binaryOp(&self, code: TCode, value_rhs : DValue, overflow: Overflow) -> Result<DValue> {
 
-        if code.isArithmeticOp() {
-            self.arithmeticOp(code, value_rhs)
+        let is_str = matches!(self, DValue::STR(_)) || matches!(value_rhs, DValue::STR(_));
+        if is_str && code == TCode::ADD {
+            self.stringOp(code, value_rhs)
+        } else if code.isArithmeticOp() {
+            self.arithmeticOp(code, value_rhs, overflow)
         } else if code.isRelationalOp() {
             self.relationalOp(code, value_rhs)
         } else if code.isLogicalOp() {
             self.logicalOp(code, value_rhs)
         } else {
-            panic!("Binary operator {:?} is undefined on values ({:?},{:?})!", code, self, value_rhs)
+            Err(Error::runtime(format!(
+                "Binary operator {:?} is undefined on values ({}, {})!", code, self, value_rhs)))
         }
 
     }
 
 
-    fn commonDNumPair(&self, code: &TCode, value_rhs : DValue) -> DNumPair {
+    fn commonDNumPair(&self, code: &TCode, value_rhs : DValue) -> Result<DNumPair> {
         let dt_lhs = self.dynamic_typ();
         let dt_rhs = value_rhs.dynamic_typ();
 
         if !(dt_lhs.isNumeric() && dt_rhs.isNumeric()) {
-            panic!("Both types need to be numeric for operator {:?}!", code)
+            return Err(Error::runtime(format!(
+                "Both types need to be numeric for operator {:?}, got ({}, {})!",
+                code, dt_lhs, dt_rhs)));
         }
         let (Typ::A(at_lhs), Typ::A(at_rhs)) = (dt_lhs, dt_rhs) else {
-            panic!("Both types need to be atomic!")
+            return Err(Error::runtime("Both types need to be atomic!"));
         };
 
         let adt_common = at_lhs.getCommon(at_rhs);
-        let dv_lhs = self.cast(adt_common);
-        let dv_rhs = value_rhs.cast(adt_common);
-
-        match adt_common {
-            ATyp::I64 => {
-                if let (DValue::I64(lhs),DValue::I64(rhs)) = (dv_lhs, dv_rhs) {
-                    DNumPair::I64(lhs, rhs)
-                } else { panic!("") }
-            }
-            ATyp::F64 => {
-                if let (DValue::F64(lhs),DValue::F64(rhs)) = (dv_lhs, dv_rhs) {
-                    DNumPair::F64(lhs, rhs)
-                } else { panic!("") }
-            }
-            _ => { panic!("") }
+        let dv_lhs = self.cast(adt_common)?;
+        let dv_rhs = value_rhs.cast(adt_common)?;
+
+        match (dv_lhs, dv_rhs) {
+            (DValue::I64(lhs), DValue::I64(rhs)) => { Ok(DNumPair::I64(lhs, rhs)) }
+            (DValue::F64(lhs), DValue::F64(rhs)) => { Ok(DNumPair::F64(lhs, rhs)) }
+            _ => { Err(Error::runtime(format!("No common numeric type for operator {:?}!", code))) }
         }
     }
 
 
-    fn arithmeticOp(&self, code: TCode, value_rhs : DValue) -> DValue {
+    /// arithmetic on numbers of common type, see Overflow::intOp() and floatOp()
+    fn arithmeticOp(&self, code: TCode, value_rhs : DValue, overflow: Overflow) -> Result<DValue> {
 
-        let num_pair = self.commonDNumPair(&code, value_rhs);
+        let num_pair = self.commonDNumPair(&code, value_rhs)?;
 
-        match code {
-            TCode::ADD => {
-                match num_pair {
-                    DNumPair::I64(l, r) => { DValue::I64(l + r) }
-                    DNumPair::F64(l, r) => { DValue::F64(l + r) }
-                }
-            }
-            TCode::SUB =>  {
-                match num_pair {
-                    DNumPair::I64(l, r) => { DValue::I64(l - r) }
-                    DNumPair::F64(l, r) => { DValue::F64(l - r) }
-                }
-            }
-            TCode::MULT => {
-                match num_pair {
-                    DNumPair::I64(l, r) => { DValue::I64(l * r) }
-                    DNumPair::F64(l, r) => { DValue::F64(l * r) }
+        match num_pair {
+            DNumPair::I64(l, r) => { overflow.intOp(&code, l, r) }
+            DNumPair::F64(l, r) => { floatOp(&code, l, r) }
+        }
+    }
+
+
+    /// compare numbers (of common type), or BOOL, CHAR and STR values of same type
+    ///
+    /// BOOL values can only be tested for (in)equality, NaN is unordered
+    fn relationalOp(&self, code: TCode, value_rhs : DValue) -> Result<DValue> {
+
+        let ordering = match (self, &value_rhs) {
+            (DValue::BOOL(l), DValue::BOOL(r)) => {
+                if code != TCode::EQ && code != TCode::NOT_EQ {
+                    return Err(Error::runtime(format!("Operator {:?} is undefined on BOOL!", code)));
                 }
+                l.partial_cmp(r)
             }
-            TCode::DIV => {
-                match num_pair {
-                    DNumPair::I64(l, r) => { DValue::I64(l / r) }
-                    DNumPair::F64(l, r) => { DValue::F64(l / r) }
+            (DValue::CHAR(l), DValue::CHAR(r)) => { l.partial_cmp(r) }
+            (DValue::STR(l), DValue::STR(r)) => { l.partial_cmp(r) }
+            _ => {
+                match self.commonDNumPair(&code, value_rhs)? {
+                    DNumPair::I64(l, r) => { l.partial_cmp(&r) }
+                    DNumPair::F64(l, r) => { l.partial_cmp(&r) }
                 }
             }
-            _ => { panic!("{:?} is not arithmetic operator!", code) }
+        };
+
+        match code {
+            TCode::LT => { Ok(DValue::BOOL(ordering == Some(Ordering::Less))) }
+            TCode::GT => { Ok(DValue::BOOL(ordering == Some(Ordering::Greater))) }
+            TCode::LE => { Ok(DValue::BOOL(matches!(ordering, Some(Ordering::Less | Ordering::Equal)))) }
+            TCode::GE => { Ok(DValue::BOOL(matches!(ordering, Some(Ordering::Greater | Ordering::Equal)))) }
+            TCode::EQ => { Ok(DValue::BOOL(ordering == Some(Ordering::Equal))) }
+            TCode::NOT_EQ => { Ok(DValue::BOOL(ordering != Some(Ordering::Equal))) }
+            _ => { Err(Error::runtime(format!("{:?} is not relational operator!", code))) }
         }
     }
 
 
-    fn relationalOp(&self, code: TCode, value_rhs : DValue) -> DValue {
-
-        let num_pair = self.commonDNumPair(&code, value_rhs);
+    /// concatenation (with any value converted to STR)
+    fn stringOp(&self, code: TCode, value_rhs : DValue) -> Result<DValue> {
 
         match code {
-            TCode::LT => {
-                match num_pair {
-                    DNumPair::I64(l, r) => { DValue::BOOL(l < r) }
-                    DNumPair::F64(l, r) => { DValue::BOOL(l < r) }
-                }
-            }
-            TCode::GT => {
-                match num_pair {
-                    DNumPair::I64(l, r) => { DValue::BOOL(l > r) }
-                    DNumPair::F64(l, r) => { DValue::BOOL(l > r) }
-                }
-            }
-            TCode::EQ => {
-                match num_pair {
-                    DNumPair::I64(l, r) => { DValue::BOOL(l == r) }
-                    DNumPair::F64(l, r) => { DValue::BOOL(l == r) }
-                }
+            TCode::ADD => {
+                let s_lhs = self.cast(ATyp::STR)?.toString();
+                let s_rhs = value_rhs.cast(ATyp::STR)?.toString();
+                Ok(DValue::STR(s_lhs + &s_rhs))
             }
-            TCode::NOT_EQ => {
-                match num_pair {
-                    DNumPair::I64(l, r) => { DValue::BOOL(l != r) }
-                    DNumPair::F64(l, r) => { DValue::BOOL(l != r) }
-                }
+            _ => { Err(Error::runtime(format!("Operator {:?} is undefined on STR!", code))) }
+        }
+    }
+
+
+    /// value as operand of logical operator (on given side, "left" or "right")
+    pub fn logicalOperand(&self, code: &TCode, side: &str) -> Result<bool> {
+        match self {
+            DValue::BOOL(b) => { Ok(*b) }
+            _ => {
+                Err(Error::runtime(format!(
+                    "Operator {:?} requires operands of type BOOL, got {} on the {}!",
+                    code, self.dynamic_typ(), side)))
             }
-            _ => {  panic!("{:?} is not relational operator!", code) }
         }
     }
 
 
-    fn logicalOp(&self, code: TCode, value_rhs : DValue) -> DValue {
+    fn logicalOp(&self, code: TCode, value_rhs : DValue) -> Result<DValue> {
 
-        let b_lhs = match self {
-            DValue::BOOL(b) => { *b }
-            _ => { panic!("Logical operator requires LHS value to be of type Bool!") }
-        };
-        let b_rhs = match value_rhs {
-            DValue::BOOL(b) => { b }
-            _ => { panic!("Logical operator requires RHS value to be of type Bool!") }
-        };
+        let b_lhs = self.logicalOperand(&code, "left")?;
+        let b_rhs = value_rhs.logicalOperand(&code, "right")?;
 
         match code {
-            TCode::OR => { DValue::BOOL(b_lhs || b_rhs) }
-            TCode::AND => { DValue::BOOL(b_lhs && b_rhs) }
-            _ => { panic!("{:?} is not logical operator!", code) }
+            TCode::OR => { Ok(DValue::BOOL(b_lhs || b_rhs)) }
+            TCode::AND => { Ok(DValue::BOOL(b_lhs && b_rhs)) }
+            _ => { Err(Error::runtime(format!("{:?} is not logical operator!", code))) }
+        }
+    }
+
+}
+
+
+/// value for messages, STR and CHAR are quoted like literals (unlike toString())
+impl fmt::Display for DValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DValue::CHAR(c) => write!(f, "{:?}", c),
+            DValue::STR(s) => write!(f, "{:?}", s),
+            _ => write!(f, "{}", self.toString()),
         }
     }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::token::TCode;
+    use super::{DValue, Overflow};
+
+    #[test]
+    fn from_input_parses_bool_int_float_char_and_string() {
+        assert!(matches!(DValue::from_input("true\n"), DValue::BOOL(true)));
+        assert!(matches!(DValue::from_input("false\r\n"), DValue::BOOL(false)));
+        assert!(matches!(DValue::from_input(" -42 \n"), DValue::I64(-42)));
+        assert!(matches!(DValue::from_input("2.5\n"), DValue::F64(f) if f == 2.5));
+        assert!(matches!(DValue::from_input("1e3"), DValue::F64(f) if f == 1000.0));
+        assert!(matches!(DValue::from_input("x\n"), DValue::CHAR('x')));
+        assert!(matches!(DValue::from_input("7"), DValue::I64(7)));
+        assert!(matches!(DValue::from_input("hello world\n"), DValue::STR(s) if s == "hello world"));
+        assert!(matches!(DValue::from_input("True\n"), DValue::STR(s) if s == "True"));
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn negative_exponent_of_int_is_error() {
+        for overflow in [Overflow::ERROR, Overflow::WRAP, Overflow::SATURATE, Overflow::PROMOTE] {
+            let error = overflow.intOp(&TCode::POW, 2, -1).unwrap_err();
+            assert_eq!(error.msg, "Negative exponent -1 of I64 power, use F64 base instead!");
+        }
+        assert!(matches!(DValue::I64(2).binaryOp(TCode::POW, DValue::I64(0), Overflow::ERROR), Ok(DValue::I64(1))));
+        assert!(matches!(DValue::F64(2.0).binaryOp(TCode::POW, DValue::I64(-1), Overflow::ERROR),
+            Ok(DValue::F64(f)) if f == 0.5));
+    }
+
+    const EXP_HUGE: i64 = 5_000_000_000;      // beyond u32::MAX
+    const EXP_HUGE_EVEN: i64 = 4_294_967_296;  // u32::MAX + 1
+
+    fn pow(overflow: Overflow, l: i64, r: i64) -> Option<DValue> {
+        overflow.intOp(&TCode::POW, l, r).ok()
+    }
+
+    #[test]
+    fn pow_with_huge_exponent_of_trivial_base() {
+        for overflow in [Overflow::ERROR, Overflow::WRAP, Overflow::SATURATE, Overflow::PROMOTE] {
+            assert!(matches!(pow(overflow, 0, EXP_HUGE), Some(DValue::I64(0))));
+            assert!(matches!(pow(overflow, 1, EXP_HUGE), Some(DValue::I64(1))));
+            assert!(matches!(pow(overflow, -1, EXP_HUGE_EVEN), Some(DValue::I64(1))));
+            assert!(matches!(pow(overflow, -1, EXP_HUGE_EVEN + 1), Some(DValue::I64(-1))));
+        }
+    }
+
+    #[test]
+    fn pow_overflow_error() {
+        assert!(matches!(pow(Overflow::ERROR, 2, 62), Some(DValue::I64(4_611_686_018_427_387_904))));
+        assert!(pow(Overflow::ERROR, 2, 63).is_none());
+        assert!(pow(Overflow::ERROR, 3, EXP_HUGE).is_none());
+    }
+
+    #[test]
+    fn pow_overflow_wrap() {
+        assert!(matches!(pow(Overflow::WRAP, 2, 63), Some(DValue::I64(i64::MIN))));
+        assert!(matches!(pow(Overflow::WRAP, 2, EXP_HUGE), Some(DValue::I64(0))));
+        // 3 ** 2^32 == (3 ** 2^16) ** 2^16
+        let expected = 3_i64.wrapping_pow(65536).wrapping_pow(65536);
+        assert!(matches!(pow(Overflow::WRAP, 3, EXP_HUGE_EVEN), Some(DValue::I64(i)) if i == expected));
+    }
+
+    #[test]
+    fn pow_overflow_saturate() {
+        assert!(matches!(pow(Overflow::SATURATE, 2, 63), Some(DValue::I64(i64::MAX))));
+        assert!(matches!(pow(Overflow::SATURATE, -2, 65), Some(DValue::I64(i64::MIN))));
+        assert!(matches!(pow(Overflow::SATURATE, -2, EXP_HUGE_EVEN), Some(DValue::I64(i64::MAX))));
+        assert!(matches!(pow(Overflow::SATURATE, -2, EXP_HUGE_EVEN + 1), Some(DValue::I64(i64::MIN))));
+    }
+
+    #[test]
+    fn pow_overflow_promote() {
+        assert!(matches!(pow(Overflow::PROMOTE, 2, 63), Some(DValue::F64(f)) if f == 2f64.powi(63)));
+        assert!(matches!(pow(Overflow::PROMOTE, 2, EXP_HUGE), Some(DValue::F64(f)) if f.is_infinite()));
+    }
+}